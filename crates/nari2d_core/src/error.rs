@@ -1,8 +1,12 @@
-use crate::geometry::point2d::Point2d;
+use crate::geometry::{
+    mesh::{EdgeId, PointId, TriangleId},
+    point2d::Point2d,
+};
+use miette::Diagnostic;
 use std::borrow::Cow;
 use thiserror::Error;
 
-#[derive(Clone, Debug, PartialEq, Error)]
+#[derive(Clone, Debug, PartialEq, Error, Diagnostic)]
 pub enum Nari2DCoreError {
     // Mesh
     #[error("Too few points on mesh: {0}")]
@@ -13,10 +17,19 @@ pub enum Nari2DCoreError {
     AlreadyExists(Point2d),
     #[error("Mesh Element {0} does not exist.")]
     DoesNotExist(Cow<'static, str>),
+    #[error("Mesh Element {0} is locked.")]
+    Locked(Cow<'static, str>),
+    #[error("This operation would invert a triangle")]
+    InvertsTriangle,
     #[error("Failed to calculate hull: {0}")]
     HullCalculation(Cow<'static, str>),
     #[error("Failed to triangulate points: {0}")]
     Triangulation(Cow<'static, str>),
+    #[error("Failed to import mesh: {0}")]
+    Import(Cow<'static, str>),
+    #[error("Mesh failed validation with {} violation(s)", .0.len())]
+    #[diagnostic(code(nari2d::mesh::invalid))]
+    InvalidMesh(#[related] Vec<MeshViolation>),
 
     // General
     #[error("General Operation Error: {0}")]
@@ -25,4 +38,66 @@ pub enum Nari2DCoreError {
     ThisIsABug(Cow<'static, str>),
 }
 
+// a single broken invariant of the mesh connectivity, found by validate()
+#[derive(Copy, Clone, Debug, PartialEq, Error, Diagnostic)]
+pub enum MeshViolation {
+    #[error("Triangle {triangle} references point {point} that does not exist")]
+    #[diagnostic(code(nari2d::mesh::missing_point))]
+    MissingPoint {
+        triangle: TriangleId,
+        point: PointId,
+    },
+    #[error("Triangle {0} has no TriangleEdge")]
+    #[diagnostic(code(nari2d::mesh::missing_triangle_edge))]
+    MissingTriangleEdge(TriangleId),
+    #[error("Triangle {triangle} references edge {edge} as side {side}, but it does not bound that side")]
+    #[diagnostic(
+        code(nari2d::mesh::wrong_triangle_edge),
+        help(
+            "side 0 is point0 -> point1, side 1 is point1 -> point2 and side 2 is point2 -> point0"
+        )
+    )]
+    WrongTriangleEdge {
+        triangle: TriangleId,
+        edge: EdgeId,
+        side: u8,
+    },
+    #[error("Edge {edge} does not reference triangle {triangle} that it bounds")]
+    #[diagnostic(code(nari2d::mesh::missing_back_reference))]
+    MissingBackReference { edge: EdgeId, triangle: TriangleId },
+    #[error("Edge {edge} references triangle {triangle} that it does not bound")]
+    #[diagnostic(code(nari2d::mesh::stale_back_reference))]
+    StaleBackReference { edge: EdgeId, triangle: TriangleId },
+    #[error("Edge {edge} is used by {count} triangles")]
+    #[diagnostic(
+        code(nari2d::mesh::non_manifold_edge),
+        help("an edge can be shared by at most two triangles")
+    )]
+    NonManifoldEdge { edge: EdgeId, count: usize },
+    #[error("Edge {edge} references point {point} that does not exist")]
+    #[diagnostic(code(nari2d::mesh::dangling_edge))]
+    DanglingEdge { edge: EdgeId, point: PointId },
+    #[error("Point {point} is missing edge {edge} from its PointEdge")]
+    #[diagnostic(code(nari2d::mesh::incomplete_point_edge))]
+    IncompletePointEdge { point: PointId, edge: EdgeId },
+    #[error("Point {point} lists edge {edge} in its PointEdge, but the edge does not touch it")]
+    #[diagnostic(code(nari2d::mesh::stale_point_edge))]
+    StalePointEdge { point: PointId, edge: EdgeId },
+    #[error("Triangle {0} is not counter clockwise")]
+    #[diagnostic(code(nari2d::mesh::wrong_winding))]
+    WrongWinding(TriangleId),
+    #[error("Edge {0} is on the boarder but not in the boarder edges")]
+    #[diagnostic(code(nari2d::mesh::missing_boarder_edge))]
+    MissingBoarderEdge(EdgeId),
+    #[error("Edge {0} is in the boarder edges but is not on the boarder")]
+    #[diagnostic(code(nari2d::mesh::stale_boarder_edge))]
+    StaleBoarderEdge(EdgeId),
+    #[error("The boarder does not form closed loops at point {0}")]
+    #[diagnostic(
+        code(nari2d::mesh::open_boarder),
+        help("every boarder point needs as many boarder edges going in as going out")
+    )]
+    OpenBoarder(PointId),
+}
+
 pub type NCResult<T> = Result<T, Nari2DCoreError>; // new california result <T>
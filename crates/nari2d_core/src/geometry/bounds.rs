@@ -1,4 +1,4 @@
-use crate::geometry::{Point2d, Scale2d};
+use crate::geometry::{point2d::Point2d, scale2d::Scale2d, transform2d::Transform2d};
 
 //  ______________A
 // |        _B__/ |
@@ -9,7 +9,7 @@ use crate::geometry::{Point2d, Scale2d};
 // B = extend
 
 #[cfg_attr(feature = "serde_impl", derive(Serialize, Deserialize))]
-#[derive(Copy, Clone, Debug, Default, PartialOrd, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 #[repr(C)]
 pub struct Bounds {
     center: Point2d,
@@ -19,7 +19,7 @@ pub struct Bounds {
 impl Bounds {
     #[inline]
     pub fn new(center: Point2d, top_right: Point2d) -> Self {
-        let extend: Scale2d = (center - top_right).into();
+        let extend: Scale2d = (top_right - center).into();
         Bounds { center, extend }
     }
 
@@ -31,14 +31,38 @@ impl Bounds {
         }
     }
 
+    #[must_use]
     #[inline]
-    pub fn from_points(min: Point2d, max: Point2d) -> Self {
+    pub fn from_min_max(min: Point2d, max: Point2d) -> Self {
         let center = (min + max) / 2_f32;
-        let extend: Scale2d = (max - center).into();
+        // rounding can leave min or max just outside of center +- extend, so grow it until
+        // they are both in
+        let along = |center: f32, min: f32, max: f32| {
+            let mut extend = (max - center).max(center - min);
+            while center - extend > min || center + extend < max {
+                extend += f32::EPSILON * center.abs().max(extend);
+            }
+            extend
+        };
+        let extend = Scale2d::new(along(center.x, min.x, max.x), along(center.y, min.y, max.y));
 
         Bounds { center, extend }
     }
 
+    // the smallest bounds around every point, None if there are none
+    #[must_use]
+    pub fn from_points(points: impl IntoIterator<Item = Point2d>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        let (min, max) = points.fold((first, first), |(min, max), point| {
+            (
+                Point2d::new(min.x.min(point.x), min.y.min(point.y)),
+                Point2d::new(max.x.max(point.x), max.y.max(point.y)),
+            )
+        });
+        Some(Bounds::from_min_max(min, max))
+    }
+
     #[inline]
     pub fn center(&self) -> Point2d {
         self.center
@@ -53,6 +77,19 @@ impl Bounds {
     pub fn center_y(&self) -> f32 {
         self.center.y()
     }
+
+    #[must_use]
+    #[inline]
+    pub fn min(&self) -> Point2d {
+        Point2d::new(self.center.x - self.extend.x, self.center.y - self.extend.y)
+    }
+
+    #[must_use]
+    #[inline]
+    pub fn max(&self) -> Point2d {
+        Point2d::new(self.center.x + self.extend.x, self.center.y + self.extend.y)
+    }
+
     #[inline]
     pub fn extends(&self) -> Scale2d {
         self.extend
@@ -60,12 +97,12 @@ impl Bounds {
 
     #[inline]
     pub fn extends_x(&self) -> f32 {
-        self.extend.x()
+        self.extend.x
     }
 
     #[inline]
     pub fn extends_y(&self) -> f32 {
-        self.extend.y()
+        self.extend.y
     }
 
     #[inline]
@@ -90,12 +127,12 @@ impl Bounds {
 
     #[inline]
     pub fn set_center_x(&mut self, new_x: f32) {
-        self.center.set_x(new_x)
+        self.center.set_x(new_x);
     }
 
     #[inline]
     pub fn set_center_y(&mut self, new_y: f32) {
-        self.center.set_y(new_y)
+        self.center.set_y(new_y);
     }
 
     #[inline]
@@ -105,11 +142,212 @@ impl Bounds {
 
     #[inline]
     pub fn set_extend_x(&mut self, new_x: f32) {
-        self.extend.set_x(new_x)
+        self.extend.x = new_x;
     }
 
     #[inline]
     pub fn set_extend_y(&mut self, new_y: f32) {
-        self.extend.set_y(new_y)
+        self.extend.y = new_y;
+    }
+
+    // the smallest bounds around both
+    #[must_use]
+    pub fn union(&self, other: &Bounds) -> Bounds {
+        let (min, max) = (self.min(), self.max());
+        let (other_min, other_max) = (other.min(), other.max());
+        Bounds::from_min_max(
+            Point2d::new(min.x.min(other_min.x), min.y.min(other_min.y)),
+            Point2d::new(max.x.max(other_max.x), max.y.max(other_max.y)),
+        )
+    }
+
+    // the part both cover, None if they do not overlap. bounds that only touch give bounds
+    // with no width or height
+    #[must_use]
+    pub fn intersection(&self, other: &Bounds) -> Option<Bounds> {
+        let (min, max) = (self.min(), self.max());
+        let (other_min, other_max) = (other.min(), other.max());
+        let low = Point2d::new(min.x.max(other_min.x), min.y.max(other_min.y));
+        let high = Point2d::new(max.x.min(other_max.x), max.y.min(other_max.y));
+        if low.x > high.x || low.y > high.y {
+            return None;
+        }
+        Some(Bounds::from_min_max(low, high))
+    }
+
+    // points on the sides are inside
+    #[must_use]
+    #[inline]
+    pub fn contains_point(&self, point: Point2d) -> bool {
+        let (min, max) = (self.min(), self.max());
+        min.x <= point.x && point.x <= max.x && min.y <= point.y && point.y <= max.y
+    }
+
+    #[must_use]
+    #[inline]
+    pub fn contains_bounds(&self, other: &Bounds) -> bool {
+        self.contains_point(other.min()) && self.contains_point(other.max())
+    }
+
+    // true if they overlap or touch
+    #[must_use]
+    #[inline]
+    pub fn intersects(&self, other: &Bounds) -> bool {
+        let (min, max) = (self.min(), self.max());
+        let (other_min, other_max) = (other.min(), other.max());
+        min.x <= other_max.x && other_min.x <= max.x && min.y <= other_max.y && other_min.y <= max.y
+    }
+
+    // grown by `padding` on every side, or shrunk if it is negative. it never shrinks past
+    // the center
+    #[must_use]
+    pub fn padded(&self, padding: f32) -> Bounds {
+        self.padded_by(Scale2d::new(padding, padding))
+    }
+
+    // grown by `padding.x` on the left and right and `padding.y` on the top and bottom
+    #[must_use]
+    pub fn padded_by(&self, padding: Scale2d) -> Bounds {
+        Bounds {
+            center: self.center,
+            extend: Scale2d::new(
+                (self.extend.x + padding.x).max(0_f32),
+                (self.extend.y + padding.y).max(0_f32),
+            ),
+        }
+    }
+
+    // the smallest bounds around the transformed corners
+    #[must_use]
+    pub fn transformed(&self, transform: &Transform2d) -> Bounds {
+        let (min, max) = (self.min(), self.max());
+        Bounds::from_points(
+            [
+                min,
+                Point2d::new(max.x, min.y),
+                max,
+                Point2d::new(min.x, max.y),
+            ]
+            .map(|corner| transform.transform_point(corner)),
+        )
+        .unwrap_or(*self)
+    }
+}
+
+impl Default for Bounds {
+    fn default() -> Self {
+        Bounds {
+            center: Point2d::default(),
+            extend: Scale2d::new(0_f32, 0_f32),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::angle::Angle;
+
+    fn bounds(min: (f32, f32), max: (f32, f32)) -> Bounds {
+        Bounds::from_min_max(Point2d::new(min.0, min.1), Point2d::new(max.0, max.1))
+    }
+
+    #[test]
+    fn new_takes_the_top_right_corner() {
+        let bounds = Bounds::new(Point2d::new(1_f32, 1_f32), Point2d::new(3_f32, 4_f32));
+        assert_eq!(bounds.extends(), Scale2d::new(2_f32, 3_f32));
+        assert_eq!(bounds.min(), Point2d::new(-1_f32, -2_f32));
+        assert_eq!(bounds.max(), Point2d::new(3_f32, 4_f32));
+        assert!(bounds.contains_point(Point2d::new(-1_f32, 4_f32)));
+    }
+
+    #[test]
+    fn union_and_intersection() {
+        let (a, b) = (
+            bounds((0_f32, 0_f32), (4_f32, 2_f32)),
+            bounds((2_f32, -1_f32), (6_f32, 1_f32)),
+        );
+        let union = a.union(&b);
+        assert_eq!(
+            (union.min(), union.max()),
+            (Point2d::new(0_f32, -1_f32), Point2d::new(6_f32, 2_f32))
+        );
+        assert!(union.contains_bounds(&a) && union.contains_bounds(&b));
+
+        let intersection = a.intersection(&b).unwrap();
+        assert_eq!(
+            (intersection.min(), intersection.max()),
+            (Point2d::new(2_f32, 0_f32), Point2d::new(4_f32, 1_f32))
+        );
+
+        // touching gives a line, apart gives nothing
+        let touching = a
+            .intersection(&bounds((4_f32, 0_f32), (5_f32, 2_f32)))
+            .unwrap();
+        assert_eq!(touching.size_x(), 0_f32);
+        assert!(a.intersects(&bounds((4_f32, 0_f32), (5_f32, 2_f32))));
+        assert_eq!(
+            a.intersection(&bounds((5_f32, 0_f32), (6_f32, 2_f32))),
+            None
+        );
+        assert!(!a.intersects(&bounds((5_f32, 0_f32), (6_f32, 2_f32))));
+    }
+
+    #[test]
+    fn contains_includes_the_sides() {
+        let a = bounds((0_f32, 0_f32), (4_f32, 2_f32));
+        assert!(a.contains_point(Point2d::new(0_f32, 0_f32)));
+        assert!(a.contains_point(Point2d::new(4_f32, 1_f32)));
+        assert!(!a.contains_point(Point2d::new(4.5, 1_f32)));
+        assert!(a.contains_bounds(&bounds((1_f32, 0_f32), (4_f32, 1_f32))));
+        assert!(!a.contains_bounds(&bounds((1_f32, 0_f32), (5_f32, 1_f32))));
+    }
+
+    #[test]
+    fn padding_grows_and_stops_shrinking_at_the_center() {
+        let a = bounds((0_f32, 0_f32), (4_f32, 2_f32));
+        let grown = a.padded(1_f32);
+        assert_eq!(
+            (grown.min(), grown.max()),
+            (Point2d::new(-1_f32, -1_f32), Point2d::new(5_f32, 3_f32))
+        );
+
+        let shrunk = a.padded(-1.5);
+        assert_eq!(shrunk.extends(), Scale2d::new(0.5, 0_f32));
+        assert_eq!(shrunk.center(), a.center());
+
+        let by = a.padded_by(Scale2d::new(1_f32, 0_f32));
+        assert_eq!(
+            (by.min(), by.max()),
+            (Point2d::new(-1_f32, 0_f32), Point2d::new(5_f32, 2_f32))
+        );
+    }
+
+    #[test]
+    fn transformed_covers_the_moved_corners() {
+        let a = bounds((0_f32, 0_f32), (4_f32, 2_f32));
+        let moved = a.transformed(&Transform2d::from_translation(Point2d::new(1_f32, -1_f32)));
+        assert_eq!(
+            (moved.min(), moved.max()),
+            (Point2d::new(1_f32, -1_f32), Point2d::new(5_f32, 1_f32))
+        );
+
+        let transform = Transform2d::from_rotation(Angle::new(std::f32::consts::FRAC_PI_4));
+        let turned = a.transformed(&transform);
+        for corner in [
+            (0_f32, 0_f32),
+            (4_f32, 0_f32),
+            (4_f32, 2_f32),
+            (0_f32, 2_f32),
+        ] {
+            let corner = transform.transform_point(Point2d::new(corner.0, corner.1));
+            assert!(
+                turned.contains_point(corner),
+                "{corner:?} is outside of {turned:?}"
+            );
+        }
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+        assert!((turned.size_x() - 6_f32 * half).abs() < 1e-4);
+        assert!((turned.size_y() - 6_f32 * half).abs() < 1e-4);
     }
 }
@@ -0,0 +1,410 @@
+use crate::{
+    error::{NCResult, Nari2DCoreError},
+    geometry::{
+        angle::Angle,
+        bounds::Bounds,
+        lattice::{Lattice, LatticeInterpolation, LatticeWeights},
+        mesh::simple_mesh::SimpleMesh,
+        point2d::Point2d,
+    },
+};
+use cgmath::{Matrix2, Vector2};
+
+// deformers stacked the way a rig nests them: a head lattice inside of a body lattice, an arm
+// rotating around its shoulder. a chain goes from the innermost deformer, the one the mesh sits
+// in, to the outermost. they all share the rest space of the mesh.
+//
+// like live2d, an outer deformer does not move the vertices but the deformer inside of it: the
+// control points of a lattice, or the origin and axes of a rotation, bent to match the outer
+// deformer around the origin. only the innermost deformer touches the vertices, so binding a
+// mesh works out their weights in its lattice once and evaluating the chain is a weighted sum
+// per vertex. outer lattices are only evaluated at the control points of the one inside of
+// them, so between those control points the inner lattice decides how the mesh bends.
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_impl", derive(Serialize, Deserialize))]
+pub struct Rotation {
+    // the point that is turned and scaled around
+    pub origin: Point2d,
+    pub angle: Angle,
+    pub scale: f32,
+    // moves the origin after turning
+    pub translation: Point2d,
+}
+
+impl Rotation {
+    #[must_use]
+    pub fn new(origin: Point2d) -> Self {
+        Rotation {
+            origin,
+            angle: Angle::ZERO,
+            scale: 1_f32,
+            translation: Point2d::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn deform(&self, point: Point2d) -> Point2d {
+        Resolved::from(*self).deform(point)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_impl", derive(Serialize, Deserialize))]
+pub enum Deformer {
+    Lattice(Lattice),
+    Rotation(Rotation),
+}
+
+impl Deformer {
+    #[must_use]
+    pub fn deform(&self, point: Point2d) -> Point2d {
+        match self {
+            Deformer::Lattice(lattice) => lattice.deform(point),
+            Deformer::Rotation(rotation) => rotation.deform(point),
+        }
+    }
+}
+
+impl From<Lattice> for Deformer {
+    fn from(lattice: Lattice) -> Self {
+        Deformer::Lattice(lattice)
+    }
+}
+
+impl From<Rotation> for Deformer {
+    fn from(rotation: Rotation) -> Self {
+        Deformer::Rotation(rotation)
+    }
+}
+
+// what the cached weights were worked out against. moving the control points is fine, changing
+// any of this is not
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct LatticeLayout {
+    rest: Bounds,
+    columns: usize,
+    rows: usize,
+    interpolation: LatticeInterpolation,
+}
+
+impl From<&Lattice> for LatticeLayout {
+    fn from(lattice: &Lattice) -> Self {
+        LatticeLayout {
+            rest: lattice.rest(),
+            columns: lattice.columns(),
+            rows: lattice.rows(),
+            interpolation: lattice.interpolation(),
+        }
+    }
+}
+
+// the rest positions of a mesh, with what the innermost deformer of a chain needs cached
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeformerBinding {
+    rest: Vec<Point2d>,
+    // the weights of every vertex in the innermost lattice. empty if the innermost deformer is
+    // not a lattice
+    weights: Vec<LatticeWeights>,
+    layout: Option<LatticeLayout>,
+}
+
+impl DeformerBinding {
+    #[must_use]
+    pub fn rest(&self) -> &[Point2d] {
+        &self.rest
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.rest.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.rest.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde_impl", derive(Serialize, Deserialize))]
+pub struct DeformerChain {
+    // innermost first
+    deformers: Vec<Deformer>,
+}
+
+impl DeformerChain {
+    #[must_use]
+    pub fn new() -> Self {
+        DeformerChain::default()
+    }
+
+    #[must_use]
+    pub fn deformers(&self) -> &[Deformer] {
+        &self.deformers
+    }
+
+    #[must_use]
+    pub fn deformer(&self, index: usize) -> Option<&Deformer> {
+        self.deformers.get(index)
+    }
+
+    // changing the rest rectangle, size or interpolation of the innermost lattice means the
+    // bindings of the chain have to be made again
+    #[must_use]
+    pub fn deformer_mut(&mut self, index: usize) -> Option<&mut Deformer> {
+        self.deformers.get_mut(index)
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.deformers.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.deformers.is_empty()
+    }
+
+    // adds a deformer around every deformer already in the chain
+    pub fn push_outer(&mut self, deformer: impl Into<Deformer>) {
+        self.deformers.push(deformer.into());
+    }
+
+    // adds a deformer inside of every deformer already in the chain
+    pub fn push_inner(&mut self, deformer: impl Into<Deformer>) {
+        self.deformers.insert(0, deformer.into());
+    }
+
+    /// # Errors
+    ///
+    /// `Nari2DCoreError::DoesNotExist` if there is no deformer at `index`.
+    pub fn remove(&mut self, index: usize) -> NCResult<Deformer> {
+        if index >= self.deformers.len() {
+            return Err(Nari2DCoreError::DoesNotExist(
+                format!("Deformer {index}").into(),
+            ));
+        }
+        Ok(self.deformers.remove(index))
+    }
+
+    // where a rest point ends up, without any caching
+    #[must_use]
+    pub fn deform(&self, point: Point2d) -> Point2d {
+        self.resolve()
+            .map_or(point, |innermost| innermost.deform(point))
+    }
+
+    // the innermost deformer, with every deformer around it applied to it
+    fn resolve(&self) -> Option<Resolved> {
+        self.deformers.iter().rev().fold(None, |outer, deformer| {
+            let resolved = match deformer {
+                Deformer::Lattice(lattice) => Resolved::Lattice(lattice.clone()),
+                Deformer::Rotation(rotation) => Resolved::from(*rotation),
+            };
+            Some(match outer {
+                Some(outer) => outer.apply_to(resolved),
+                None => resolved,
+            })
+        })
+    }
+
+    #[must_use]
+    pub fn bind(&self, rest: &[Point2d]) -> DeformerBinding {
+        match self.deformers.first() {
+            Some(Deformer::Lattice(lattice)) => DeformerBinding {
+                rest: rest.to_vec(),
+                weights: rest.iter().map(|point| lattice.weights(*point)).collect(),
+                layout: Some(lattice.into()),
+            },
+            _ => DeformerBinding {
+                rest: rest.to_vec(),
+                weights: vec![],
+                layout: None,
+            },
+        }
+    }
+
+    #[must_use]
+    pub fn bind_mesh(&self, mesh: &SimpleMesh) -> DeformerBinding {
+        self.bind(mesh.positions())
+    }
+
+    // where every bound vertex ends up, in the order they were bound in. fails if the innermost
+    // deformer is no longer laid out the way it was when the binding was made
+    /// # Errors
+    ///
+    /// `Nari2DCoreError::General` if the innermost deformer is not laid out the way it was when the
+    /// binding was made.
+    pub fn evaluate(&self, binding: &DeformerBinding) -> NCResult<Vec<Point2d>> {
+        let layout = match self.deformers.first() {
+            Some(Deformer::Lattice(lattice)) => Some(LatticeLayout::from(lattice)),
+            _ => None,
+        };
+        if layout != binding.layout {
+            return Err(Nari2DCoreError::General(
+                "The deformer chain changed since the binding was made".into(),
+            ));
+        }
+
+        Ok(match self.resolve() {
+            Some(Resolved::Lattice(lattice)) => binding
+                .weights
+                .iter()
+                .map(|weights| lattice.apply(weights))
+                .collect(),
+            Some(affine @ Resolved::Affine { .. }) => binding
+                .rest
+                .iter()
+                .map(|point| affine.deform(*point))
+                .collect(),
+            None => binding.rest.clone(),
+        })
+    }
+}
+
+// a deformer after every deformer around it was applied to it
+#[derive(Clone, Debug)]
+enum Resolved {
+    Lattice(Lattice),
+    Affine {
+        linear: Matrix2<f32>,
+        offset: Point2d,
+        // where the origin of the rotation ended up, outer deformers are bent to match there
+        pivot: Point2d,
+    },
+}
+
+impl Resolved {
+    fn deform(&self, point: Point2d) -> Point2d {
+        match self {
+            Resolved::Lattice(lattice) => lattice.deform(point),
+            Resolved::Affine { linear, offset, .. } => *offset + multiply(linear, point),
+        }
+    }
+
+    // how the deformer stretches and turns things around the point
+    fn jacobian(&self, at: Point2d) -> Matrix2<f32> {
+        match self {
+            Resolved::Lattice(lattice) => {
+                let size = lattice.rest().size();
+                let step = size.x.max(size.y).max(f32::EPSILON) * 1e-3;
+                let along = |offset: Point2d| {
+                    (self.deform(at + offset) - self.deform(at - offset)) / (step * 2_f32)
+                };
+                let (x, y) = (
+                    along(Point2d::new(step, 0_f32)),
+                    along(Point2d::new(0_f32, step)),
+                );
+                Matrix2::new(x.x, x.y, y.x, y.y)
+            }
+            Resolved::Affine { linear, .. } => *linear,
+        }
+    }
+
+    // moves the inner deformer with this one: every control point of a lattice, or the origin of
+    // an affine with its axes bent the way this deformer bends them there
+    fn apply_to(&self, inner: Resolved) -> Resolved {
+        match inner {
+            Resolved::Lattice(mut lattice) => {
+                lattice.map_points(|point| self.deform(point));
+                Resolved::Lattice(lattice)
+            }
+            Resolved::Affine {
+                linear,
+                offset,
+                pivot,
+            } => {
+                let jacobian = self.jacobian(pivot);
+                let moved = self.deform(pivot);
+                Resolved::Affine {
+                    linear: jacobian * linear,
+                    offset: moved + multiply(&jacobian, offset - pivot),
+                    pivot: moved,
+                }
+            }
+        }
+    }
+}
+
+impl From<Rotation> for Resolved {
+    fn from(rotation: Rotation) -> Self {
+        let (sin, cos) = rotation.angle.0.sin_cos();
+        let linear = Matrix2::new(cos, sin, -sin, cos) * rotation.scale;
+        let pivot = rotation.origin + rotation.translation;
+        Resolved::Affine {
+            linear,
+            offset: pivot - multiply(&linear, rotation.origin),
+            pivot,
+        }
+    }
+}
+
+fn multiply(matrix: &Matrix2<f32>, point: Point2d) -> Point2d {
+    let moved = matrix * Vector2::new(point.x, point.y);
+    Point2d::new(moved.x, moved.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lattice() -> Lattice {
+        let rest = Bounds::from_min_max(Point2d::new(0_f32, 0_f32), Point2d::new(4_f32, 4_f32));
+        Lattice::new(rest, 5, 5, LatticeInterpolation::Bilinear).unwrap()
+    }
+
+    fn samples() -> Vec<Point2d> {
+        (0..=8_u8)
+            .flat_map(|x| (0..=8_u8).map(move |y| Point2d::new(f32::from(x), f32::from(y)) * 0.5))
+            .collect()
+    }
+
+    #[test]
+    fn rest_chain_leaves_points_where_they_are() {
+        let mut chain = DeformerChain::new();
+        chain.push_outer(lattice());
+        chain.push_outer(Rotation::new(Point2d::new(2_f32, 0_f32)));
+        chain.push_outer(lattice());
+
+        let rest = samples();
+        let evaluated = chain.evaluate(&chain.bind(&rest)).unwrap();
+        for (point, deformed) in rest.iter().zip(evaluated) {
+            assert!(
+                deformed.distance(point) < 1e-4,
+                "{point:?} moved to {deformed:?}"
+            );
+            assert!(chain.deform(*point).distance(point) < 1e-4);
+        }
+    }
+
+    #[test]
+    fn lattice_inside_rotation_is_the_lattice_then_the_rotation() {
+        let mut inner = lattice();
+        inner
+            .set_point(1, 2, inner.rest_point(1, 2) + Point2d::new(0.4, -0.3))
+            .unwrap();
+        inner
+            .set_point(3, 3, inner.rest_point(3, 3) + Point2d::new(-0.2, 0.5))
+            .unwrap();
+        let mut rotation = Rotation::new(Point2d::new(2_f32, 0_f32));
+        rotation.angle = Angle::new(0.7);
+        rotation.scale = 1.5;
+        rotation.translation = Point2d::new(1_f32, -2_f32);
+
+        let mut chain = DeformerChain::new();
+        chain.push_outer(inner.clone());
+        chain.push_outer(rotation);
+
+        let rest = samples();
+        let evaluated = chain.evaluate(&chain.bind(&rest)).unwrap();
+        for (point, deformed) in rest.iter().zip(evaluated) {
+            let expected = rotation.deform(inner.deform(*point));
+            assert!(
+                deformed.distance(&expected) < 1e-4,
+                "{point:?} went to {deformed:?} instead of {expected:?}"
+            );
+            assert!(chain.deform(*point).distance(&expected) < 1e-4);
+        }
+    }
+}
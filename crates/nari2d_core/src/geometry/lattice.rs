@@ -1 +1,448 @@
+use crate::{
+    error::{NCResult, Nari2DCoreError},
+    geometry::{bounds::Bounds, point2d::Point2d},
+};
+use simple_grid::{Grid, GridIndex};
+use smallvec::SmallVec;
 
+// free form deformation: a grid of control points laid over a rest rectangle. moving the
+// control points warps everything inside of the rectangle with them. points outside of it are
+// extrapolated from the closest cells.
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_impl", derive(Serialize, Deserialize))]
+pub enum LatticeInterpolation {
+    // every cell is warped on its own, so the lattice lines stay straight
+    Bilinear,
+    // bicubic bezier patches over blocks of 4 by 4 control points, sharing the control points
+    // on their sides. the lattice goes through the corners of every patch and is smooth inside
+    // of them. when a side does not have 3n + 1 control points the last patch along it is
+    // quadratic or linear
+    Bezier,
+    // uniform cubic b-splines, smooth across cells while every control point only pulls on the
+    // cells around it. the lattice does not go through its control points
+    BSpline,
+}
+
+// the control points a point of the rest rectangle depends on, and how much. never more than 16
+pub type LatticeWeights = SmallVec<[(GridIndex, f32); 16]>;
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_impl", derive(Serialize, Deserialize))]
+pub struct Lattice {
+    rest: Bounds,
+    // column 0 is the left side and row 0 the top side of the rest rectangle
+    points: Grid<Point2d>,
+    interpolation: LatticeInterpolation,
+}
+
+impl Lattice {
+    // a lattice at rest, with its control points evenly spread over `rest`
+    /// # Errors
+    ///
+    /// `Nari2DCoreError::General` with fewer than 2 columns or rows.
+    pub fn new(
+        rest: Bounds,
+        columns: usize,
+        rows: usize,
+        interpolation: LatticeInterpolation,
+    ) -> NCResult<Self> {
+        if columns < 2 || rows < 2 {
+            return Err(Nari2DCoreError::General(
+                "A lattice needs at least 2 columns and 2 rows".into(),
+            ));
+        }
+        let mut lattice = Lattice {
+            rest,
+            points: Grid::new(columns, rows, vec![Point2d::default(); columns * rows]),
+            interpolation,
+        };
+        lattice.reset();
+        Ok(lattice)
+    }
+
+    #[must_use]
+    pub fn rest(&self) -> Bounds {
+        self.rest
+    }
+
+    #[must_use]
+    pub fn columns(&self) -> usize {
+        self.points.width()
+    }
+
+    #[must_use]
+    pub fn rows(&self) -> usize {
+        self.points.height()
+    }
+
+    #[must_use]
+    pub fn interpolation(&self) -> LatticeInterpolation {
+        self.interpolation
+    }
+
+    pub fn set_interpolation(&mut self, interpolation: LatticeInterpolation) {
+        self.interpolation = interpolation;
+    }
+
+    #[must_use]
+    pub fn points(&self) -> &Grid<Point2d> {
+        &self.points
+    }
+
+    #[must_use]
+    pub fn point(&self, column: usize, row: usize) -> Option<&Point2d> {
+        self.points.get(GridIndex::new(column, row))
+    }
+
+    /// # Errors
+    ///
+    /// `Nari2DCoreError::DoesNotExist` if there is no control point at `column`, `row`.
+    pub fn set_point(&mut self, column: usize, row: usize, point: Point2d) -> NCResult<()> {
+        *self
+            .points
+            .get_mut(GridIndex::new(column, row))
+            .ok_or_else(|| {
+                Nari2DCoreError::DoesNotExist(format!("Lattice point {column}, {row}").into())
+            })? = point;
+        Ok(())
+    }
+
+    // where the control point is when the lattice is at rest
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn rest_point(&self, column: usize, row: usize) -> Point2d {
+        let (min, size) = (self.rest.min(), self.rest.size());
+        Point2d::new(
+            min.x + size.x * column as f32 / (self.columns() - 1) as f32,
+            min.y + size.y * row as f32 / (self.rows() - 1) as f32,
+        )
+    }
+
+    // moves every control point
+    pub fn map_points(&mut self, mut map: impl FnMut(Point2d) -> Point2d) {
+        for index in self.points.indices().collect::<Vec<_>>() {
+            self.points[index] = map(self.points[index]);
+        }
+    }
+
+    // puts every control point back to where it is at rest
+    pub fn reset(&mut self) {
+        for index in self.points.indices().collect::<Vec<_>>() {
+            self.points[index] = self.rest_point(index.column(), index.row());
+        }
+    }
+
+    // where a point of the rest rectangle ends up
+    #[must_use]
+    pub fn deform(&self, point: Point2d) -> Point2d {
+        self.apply(&self.weights(point))
+    }
+
+    // every mode is a weighted sum of the control points, so where a point ends up can be
+    // worked out once at rest and reused for as long as the rest rectangle and the size of the
+    // lattice stay the same
+    #[must_use]
+    pub fn weights(&self, point: Point2d) -> LatticeWeights {
+        let (column, row) = self.to_grid(point);
+        match self.interpolation {
+            LatticeInterpolation::Bilinear => self.bilinear(column, row),
+            LatticeInterpolation::Bezier => self.bezier(column, row),
+            LatticeInterpolation::BSpline => self.b_spline(column, row),
+        }
+    }
+
+    // the weighted sum of the control points. weights of control points the lattice does not
+    // have are ignored
+    #[must_use]
+    pub fn apply(&self, weights: &LatticeWeights) -> Point2d {
+        weights
+            .iter()
+            .filter_map(|(index, weight)| Some(*self.points.get(*index)? * *weight))
+            .fold(Point2d::default(), |sum, point| sum + point)
+    }
+
+    // the point of the rest rectangle that ends up at `deformed`, found with newton's method
+    // starting from the closest control point. None if it does not converge, which happens
+    // when the lattice is folded over itself.
+    #[must_use]
+    pub fn inverse(&self, deformed: Point2d) -> Option<Point2d> {
+        let size = self.rest.size();
+        let tolerance = size.x.max(size.y) * 1e-5;
+        let step = size.x.max(size.y) * 1e-3;
+
+        let mut guess = self
+            .points
+            .cells_with_indices_iter()
+            .min_by(|(_, a), (_, b)| {
+                f32::total_cmp(&a.distance2(&deformed), &b.distance2(&deformed))
+            })
+            .map(|(index, _)| self.rest_point(index.column(), index.row()))?;
+
+        for _ in 0..32 {
+            let error = self.deform(guess) - deformed;
+            if error.x.abs().max(error.y.abs()) <= tolerance {
+                return Some(guess);
+            }
+
+            let dx = (self.deform(guess + Point2d::new(step, 0_f32))
+                - self.deform(guess - Point2d::new(step, 0_f32)))
+                / (step * 2_f32);
+            let dy = (self.deform(guess + Point2d::new(0_f32, step))
+                - self.deform(guess - Point2d::new(0_f32, step)))
+                / (step * 2_f32);
+            let determinant = dx.x * dy.y - dy.x * dx.y;
+            if determinant.abs() <= f32::EPSILON {
+                return None;
+            }
+            guess = guess
+                - Point2d::new(
+                    (dy.y * error.x - dy.x * error.y) / determinant,
+                    (dx.x * error.y - dx.y * error.x) / determinant,
+                );
+        }
+        None
+    }
+
+    // the point in grid units, (0, 0) being the first control point and (columns - 1,
+    // rows - 1) the last one
+    #[allow(clippy::cast_precision_loss)]
+    fn to_grid(&self, point: Point2d) -> (f32, f32) {
+        let (min, size) = (self.rest.min(), self.rest.size());
+        let along = |offset: f32, length: f32, count: usize| {
+            if length == 0_f32 {
+                0_f32
+            } else {
+                offset / length * (count - 1) as f32
+            }
+        };
+        (
+            along(point.x - min.x, size.x, self.columns()),
+            along(point.y - min.y, size.y, self.rows()),
+        )
+    }
+
+    // the cell the grid coordinate is in, along with where in the cell it is. past the sides of
+    // the lattice the closest cell is used, so the position goes past 0 to 1
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    fn cell(coordinate: f32, count: usize) -> (usize, f32) {
+        let cell = (coordinate.floor().max(0_f32) as usize).min(count - 2);
+        (cell, coordinate - cell as f32)
+    }
+
+    fn bilinear(&self, column: f32, row: f32) -> LatticeWeights {
+        let (column, u) = Self::cell(column, self.columns());
+        let (row, v) = Self::cell(row, self.rows());
+
+        let mut weights = LatticeWeights::new();
+        for (row, weight_v) in [(row, 1_f32 - v), (row + 1, v)] {
+            for (column, weight_u) in [(column, 1_f32 - u), (column + 1, u)] {
+                add_weight(
+                    &mut weights,
+                    GridIndex::new(column, row),
+                    weight_u * weight_v,
+                );
+            }
+        }
+        weights
+    }
+
+    fn bezier(&self, column: f32, row: f32) -> LatticeWeights {
+        let (column, weights_u) = bezier_patch(column, self.columns());
+        let (row, weights_v) = bezier_patch(row, self.rows());
+
+        let mut weights = LatticeWeights::new();
+        for (row_offset, weight_v) in weights_v.iter().enumerate() {
+            for (column_offset, weight_u) in weights_u.iter().enumerate() {
+                add_weight(
+                    &mut weights,
+                    GridIndex::new(column + column_offset, row + row_offset),
+                    weight_u * weight_v,
+                );
+            }
+        }
+        weights
+    }
+
+    fn b_spline(&self, column: f32, row: f32) -> LatticeWeights {
+        let (column, u) = Self::cell(column, self.columns());
+        let (row, v) = Self::cell(row, self.rows());
+        let (weights_u, weights_v) = (b_spline_weights(u), b_spline_weights(v));
+
+        let mut weights = LatticeWeights::new();
+        for (row_offset, weight_v) in (0..4).zip(weights_v) {
+            for (column_offset, weight_u) in (0..4).zip(weights_u) {
+                // one control point before the cell and two after it
+                self.extended(
+                    &mut weights,
+                    (column + column_offset).checked_sub(1),
+                    (row + row_offset).checked_sub(1),
+                    weight_u * weight_v,
+                );
+            }
+        }
+        weights
+    }
+
+    // adds a control point, with the lattice continued in a straight line past its sides so the
+    // b-spline reaches them. None is the one before the first
+    fn extended(
+        &self,
+        weights: &mut LatticeWeights,
+        column: Option<usize>,
+        row: Option<usize>,
+        weight: f32,
+    ) {
+        // the points that make it up along one side, with their weights
+        let line = |index: Option<usize>, count: usize| match index {
+            Some(index) if index < count => [(index, 1_f32), (index, 0_f32)],
+            Some(_) => [(count - 1, 2_f32), (count - 2, -1_f32)],
+            None => [(0, 2_f32), (1, -1_f32)],
+        };
+
+        for (column, weight_column) in line(column, self.columns()) {
+            for (row, weight_row) in line(row, self.rows()) {
+                add_weight(
+                    weights,
+                    GridIndex::new(column, row),
+                    weight * weight_column * weight_row,
+                );
+            }
+        }
+    }
+}
+
+// merges the weight into the one already there for the control point
+fn add_weight(weights: &mut LatticeWeights, index: GridIndex, weight: f32) {
+    if weight == 0_f32 {
+        return;
+    }
+    match weights.iter_mut().find(|(existing, _)| *existing == index) {
+        Some((_, existing)) => *existing += weight,
+        None => weights.push((index, weight)),
+    }
+}
+
+// the first control point of the patch the grid coordinate is in, along with the weights of the
+// control points of the patch. patches are 3 cells wide, the last one can be narrower
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn bezier_patch(coordinate: f32, count: usize) -> (usize, SmallVec<[f32; 4]>) {
+    let last = (count - 2) / 3 * 3;
+    let start = ((coordinate / 3_f32).floor().max(0_f32) as usize * 3).min(last);
+    let degree = (count - 1 - start).min(3);
+    let t = (coordinate - start as f32) / degree as f32;
+
+    // de casteljau on the weights instead of the points
+    let mut weights = SmallVec::from_elem(0_f32, degree + 1);
+    weights[0] = 1_f32;
+    for step in 1..=degree {
+        for index in (0..=step).rev() {
+            let from_before = if index > 0 {
+                weights[index - 1] * t
+            } else {
+                0_f32
+            };
+            weights[index] = weights[index] * (1_f32 - t) + from_before;
+        }
+    }
+    (start, weights)
+}
+
+fn b_spline_weights(t: f32) -> [f32; 4] {
+    let (t2, t3) = (t * t, t * t * t);
+    [
+        (1_f32 - t).powi(3) / 6_f32,
+        (3_f32 * t3 - 6_f32 * t2 + 4_f32) / 6_f32,
+        (-3_f32 * t3 + 3_f32 * t2 + 3_f32 * t + 1_f32) / 6_f32,
+        t3 / 6_f32,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODES: [LatticeInterpolation; 3] = [
+        LatticeInterpolation::Bilinear,
+        LatticeInterpolation::Bezier,
+        LatticeInterpolation::BSpline,
+    ];
+
+    // 10 by 10, so a grid coordinate of the rest rectangle is its position over the spacing
+    fn lattice(columns: usize, rows: usize, interpolation: LatticeInterpolation) -> Lattice {
+        let rest = Bounds::from_min_max(Point2d::new(0_f32, 0_f32), Point2d::new(10_f32, 10_f32));
+        Lattice::new(rest, columns, rows, interpolation).unwrap()
+    }
+
+    fn samples() -> impl Iterator<Item = Point2d> {
+        (0..=8_u8).flat_map(|x| {
+            (0..=8_u8).map(move |y| Point2d::new(f32::from(x) * 1.25, f32::from(y) * 1.25))
+        })
+    }
+
+    #[test]
+    fn rest_lattice_leaves_points_where_they_are() {
+        for mode in MODES {
+            let lattice = lattice(7, 5, mode);
+            for point in samples() {
+                let deformed = lattice.deform(point);
+                assert!(
+                    deformed.distance(&point) < 1e-4,
+                    "{mode:?} moved {point:?} to {deformed:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_finds_the_deformed_point() {
+        for mode in MODES {
+            let mut lattice = lattice(7, 5, mode);
+            lattice
+                .set_point(2, 1, lattice.rest_point(2, 1) + Point2d::new(0.6, -0.4))
+                .unwrap();
+            lattice
+                .set_point(4, 3, lattice.rest_point(4, 3) + Point2d::new(-0.5, 0.7))
+                .unwrap();
+            lattice.map_points(|point| point + Point2d::new(point.y * 0.1, 0_f32));
+            for point in samples() {
+                let found = lattice.inverse(lattice.deform(point)).unwrap();
+                assert!(
+                    found.distance(&point) < 1e-3,
+                    "{mode:?} found {found:?} for {point:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn b_spline_handle_only_moves_its_own_cells() {
+        let mut lattice = lattice(11, 11, LatticeInterpolation::BSpline);
+        lattice
+            .set_point(5, 5, lattice.rest_point(5, 5) + Point2d::new(1_f32, 1_f32))
+            .unwrap();
+
+        // control point 5 pulls on cells 3 to 6, which are grid coordinates 3 to 7
+        assert!(lattice.deform(Point2d::new(5_f32, 5_f32)) != Point2d::new(5_f32, 5_f32));
+        assert!(lattice.deform(Point2d::new(3.5, 6.5)) != Point2d::new(3.5, 6.5));
+        for point in samples() {
+            let inside = |coordinate: f32| 3_f32 < coordinate && coordinate < 7_f32;
+            if inside(point.x) && inside(point.y) {
+                continue;
+            }
+            let deformed = lattice.deform(point);
+            assert!(
+                deformed.distance(&point) < 1e-4,
+                "{point:?} moved to {deformed:?}"
+            );
+        }
+    }
+}
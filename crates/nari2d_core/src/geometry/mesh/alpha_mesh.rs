@@ -0,0 +1,327 @@
+use crate::{
+    asset::AssetRawHold,
+    error::{NCResult, Nari2DCoreError},
+    geometry::{bounds::Bounds, mesh::edit_mesh::EditMesh, point2d::Point2d, polygon::Polygon},
+};
+use image::{ImageBuffer, Rgba};
+use itertools::Itertools;
+use std::collections::HashMap;
+
+// generates a mesh for the visible part of an image, so riggers don't have to place every point
+// by hand. the mesh is in pixel coordinates of the image.
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AlphaMeshOptions {
+    // pixels with at least this alpha are part of the mesh
+    pub alpha_threshold: u8,
+    // how far the simplified outline may be from the traced one, in pixels
+    pub tolerance: f32,
+    // distance between the points inside of the outline, None only uses the outline
+    pub spacing: Option<f32>,
+    // outlines with less area than this are dropped as noise, in pixels
+    pub min_area: f32,
+}
+
+impl Default for AlphaMeshOptions {
+    fn default() -> Self {
+        Self {
+            alpha_threshold: 1,
+            tolerance: 1.5,
+            spacing: Some(32_f32),
+            min_area: 4_f32,
+        }
+    }
+}
+
+impl EditMesh {
+    // traces the outlines of the pixels above the alpha threshold with marching squares,
+    // simplifies them, fills them with evenly spaced points and triangulates with the outlines
+    // as constraints. transparent holes are left out of the mesh.
+    /// # Errors
+    ///
+    /// - `Nari2DCoreError::General` if the image is not RGBA.
+    /// - `Nari2DCoreError::TooFewPoints` if no pixel is above the alpha threshold.
+    /// - `Nari2DCoreError::Triangulation` or `Nari2DCoreError::AlreadyExists` if the outlines can't
+    ///   be triangulated, even unsimplified.
+    pub fn from_alpha(image: &AssetRawHold, options: AlphaMeshOptions) -> NCResult<EditMesh> {
+        let AssetRawHold::ImageRGBA { data, .. } = image else {
+            return Err(Nari2DCoreError::General(
+                "Only RGBA images can be meshed".into(),
+            ));
+        };
+
+        let mask = Mask::new(data, options.alpha_threshold);
+        let contours = mask.contours();
+        let mut tolerance = options.tolerance.max(0_f32);
+
+        loop {
+            match mesh_contours(&mask, &contours, tolerance, options) {
+                Ok(mesh) => return Ok(mesh),
+                // simplifying can make outlines cross each other, the traced ones never do
+                Err(Nari2DCoreError::Triangulation(_)) if tolerance > 0_f32 => {
+                    tolerance = if tolerance < 0.25 {
+                        0_f32
+                    } else {
+                        tolerance / 2_f32
+                    };
+                }
+                Err(why) => return Err(why),
+            }
+        }
+    }
+}
+
+fn mesh_contours(
+    mask: &Mask,
+    contours: &[Polygon],
+    tolerance: f32,
+    options: AlphaMeshOptions,
+) -> NCResult<EditMesh> {
+    let outlines = contours
+        .iter()
+        .map(|contour| contour.simplify_douglas_peucker(tolerance))
+        .filter(|outline| outline.len() >= 3 && outline.area() >= options.min_area)
+        .collect_vec();
+    if outlines.is_empty() {
+        return Err(Nari2DCoreError::TooFewPoints(0));
+    }
+
+    let mut mesh = EditMesh::new();
+    let mut constraints = vec![];
+    let mut holes = vec![];
+
+    for outline in &outlines {
+        let ids = outline
+            .points()
+            .iter()
+            .map(|point| mesh.insert_point(*point))
+            .collect_vec();
+        constraints.extend(ids.into_iter().circular_tuple_windows::<(_, _)>());
+
+        // outlines go counter clockwise around the opaque pixels, so holes go clockwise
+        if outline.signed_area() < 0_f32 {
+            holes.extend(hole_seed(outline, &outlines));
+        }
+    }
+
+    if let Some(spacing) = options.spacing.filter(|spacing| *spacing > 0_f32) {
+        // the outline runs along the pixel edges, half a pixel from the pixel centers
+        let clearance = spacing / 2_f32 + tolerance + 0.5;
+        for point in mask.interior_samples(spacing) {
+            if mask.clearance_at(point) >= clearance {
+                mesh.insert_point(point);
+            }
+        }
+    }
+
+    mesh.retriangulate_constrained(&constraints, &holes, true)?;
+    mesh.generate_uvs(Bounds::from_min_max(Point2d::default(), mask.size()));
+    Ok(mesh)
+}
+
+// a point inside of the hole outline but outside of every other outline, for carving the hole.
+// the corner of a convex vertex of the hole is shrunk until no other outline reaches into it.
+fn hole_seed(hole: &Polygon, outlines: &[Polygon]) -> Option<Point2d> {
+    let is_empty = |a: &Point2d, b: &Point2d, c: &Point2d| {
+        outlines.iter().flat_map(Polygon::points).all(|point| {
+            !(Point2d::orientation(a, b, point).is_clockwise()
+                && Point2d::orientation(b, c, point).is_clockwise()
+                && Point2d::orientation(c, a, point).is_clockwise())
+        })
+    };
+
+    hole.points()
+        .iter()
+        .circular_tuple_windows()
+        .filter(|(prev, current, next)| Point2d::orientation(prev, current, next).is_clockwise())
+        .find_map(|(prev, current, next)| {
+            (0..8)
+                .map(|shrink| 0.5_f32.powi(shrink))
+                .map(|scale| {
+                    (
+                        *current + (*prev - *current) * scale,
+                        *current + (*next - *current) * scale,
+                    )
+                })
+                .find(|(prev, next)| is_empty(prev, current, next))
+                .map(|(prev, next)| (prev + *current + next) / 3_f32)
+        })
+}
+
+// which pixels are opaque, along with how far each one is from the nearest transparent pixel
+struct Mask {
+    width: usize,
+    height: usize,
+    opaque: Vec<bool>,
+    clearance: Vec<f32>,
+}
+
+impl Mask {
+    fn new(data: &ImageBuffer<Rgba<u8>, Vec<u8>>, alpha_threshold: u8) -> Self {
+        let width = data.width() as usize;
+        let height = data.height() as usize;
+        let opaque = data
+            .pixels()
+            .map(|pixel| pixel[3] >= alpha_threshold)
+            .collect_vec();
+
+        let mut mask = Mask {
+            width,
+            height,
+            opaque,
+            clearance: vec![],
+        };
+        mask.clearance = mask.distance_transform();
+        mask
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn size(&self) -> Point2d {
+        Point2d::new(self.width as f32, self.height as f32)
+    }
+
+    // grid point (x, y) is the center of pixel (x - 1, y - 1), pixels outside of the image are
+    // transparent
+    fn is_opaque(&self, x: usize, y: usize) -> bool {
+        match (x.checked_sub(1), y.checked_sub(1)) {
+            (Some(x), Some(y)) if x < self.width && y < self.height => {
+                self.opaque[y * self.width + x]
+            }
+            _ => false,
+        }
+    }
+
+    // two pass chamfer distance to the nearest transparent pixel
+    fn distance_transform(&self) -> Vec<f32> {
+        let diagonal = std::f32::consts::SQRT_2;
+        let mut distance = self
+            .opaque
+            .iter()
+            .map(|opaque| if *opaque { f32::INFINITY } else { 0_f32 })
+            .collect_vec();
+        let at = |distance: &[f32], x: usize, dx: isize, y: usize, dy: isize| -> f32 {
+            match (x.checked_add_signed(dx), y.checked_add_signed(dy)) {
+                (Some(x), Some(y)) if x < self.width && y < self.height => {
+                    distance[y * self.width + x]
+                }
+                _ => 0_f32,
+            }
+        };
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let nearest = [
+                    at(&distance, x, -1, y, 0) + 1_f32,
+                    at(&distance, x, 0, y, -1) + 1_f32,
+                    at(&distance, x, -1, y, -1) + diagonal,
+                    at(&distance, x, 1, y, -1) + diagonal,
+                ]
+                .into_iter()
+                .fold(distance[y * self.width + x], f32::min);
+                distance[y * self.width + x] = nearest;
+            }
+        }
+        for y in (0..self.height).rev() {
+            for x in (0..self.width).rev() {
+                let nearest = [
+                    at(&distance, x, 1, y, 0) + 1_f32,
+                    at(&distance, x, 0, y, 1) + 1_f32,
+                    at(&distance, x, 1, y, 1) + diagonal,
+                    at(&distance, x, -1, y, 1) + diagonal,
+                ]
+                .into_iter()
+                .fold(distance[y * self.width + x], f32::min);
+                distance[y * self.width + x] = nearest;
+            }
+        }
+
+        distance
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn clearance_at(&self, point: Point2d) -> f32 {
+        if point.x < 0_f32 || point.y < 0_f32 {
+            return 0_f32;
+        }
+        let (x, y) = (point.x as usize, point.y as usize);
+        if x < self.width && y < self.height {
+            self.clearance[y * self.width + x]
+        } else {
+            0_f32
+        }
+    }
+
+    // a triangular grid of points over the image, which triangulates into equilateral triangles
+    #[allow(clippy::cast_precision_loss)]
+    fn interior_samples(&self, spacing: f32) -> impl Iterator<Item = Point2d> {
+        let (width, height) = (self.width as f32, self.height as f32);
+        let row_height = spacing * 3_f32.sqrt() / 2_f32;
+
+        (0..)
+            .map(move |row| (row, spacing / 2_f32 + row as f32 * row_height))
+            .take_while(move |(_, y)| *y < height)
+            .flat_map(move |(row, y)| {
+                let offset = if row % 2 == 0 { 0_f32 } else { spacing / 2_f32 };
+                (0..)
+                    .map(move |column| offset + spacing / 2_f32 + column as f32 * spacing)
+                    .take_while(move |x| *x < width)
+                    .map(move |x| Point2d::new(x, y))
+            })
+    }
+
+    // marching squares over the pixel centers, with the image padded by a transparent pixel.
+    // every outline has the opaque pixels on its left, saddles keep the opaque pixels connected.
+    fn contours(&self) -> Vec<Polygon> {
+        // points are keyed by twice their pixel coordinates, so they are always whole
+        let mut next = HashMap::new();
+        let mut starts = vec![];
+
+        for cell_y in 0..=self.height {
+            for cell_x in 0..=self.width {
+                // counter clockwise
+                let corners = [
+                    (cell_x, cell_y),
+                    (cell_x + 1, cell_y),
+                    (cell_x + 1, cell_y + 1),
+                    (cell_x, cell_y + 1),
+                ];
+                let opaque = corners.map(|(x, y)| self.is_opaque(x, y));
+                // only called for sides with an opaque corner, which is never on the padding
+                let midpoint = |side: usize| {
+                    let ((x0, y0), (x1, y1)) = (corners[side], corners[(side + 1) % 4]);
+                    (x0 + x1 - 1, y0 + y1 - 1)
+                };
+                let leaves = |side: usize| opaque[side] && !opaque[(side + 1) % 4];
+                let enters = |side: usize| !opaque[side] && opaque[(side + 1) % 4];
+
+                for side in (0..4).filter(|side| leaves(*side)) {
+                    let Some(to) = (1..4).map(|step| (side + step) % 4).find(|to| enters(*to))
+                    else {
+                        continue;
+                    };
+                    next.insert(midpoint(side), midpoint(to));
+                    starts.push(midpoint(side));
+                }
+            }
+        }
+
+        let mut contours = vec![];
+        for start in starts {
+            let mut contour = vec![];
+            let mut current = start;
+            while let Some(to) = next.remove(&current) {
+                contour.push(key_to_point(current));
+                current = to;
+            }
+            if !contour.is_empty() {
+                contours.push(Polygon::new(contour));
+            }
+        }
+        contours
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn key_to_point((x, y): (usize, usize)) -> Point2d {
+    Point2d::new(x as f32 / 2_f32, y as f32 / 2_f32)
+}
@@ -0,0 +1,158 @@
+use crate::{
+    error::{NCResult, Nari2DCoreError},
+    geometry::{
+        mesh::{edit_mesh::EditMesh, EdgeId, PointId, TriangleId},
+        point2d::Point2d,
+    },
+};
+use slotmap::SecondaryMap;
+
+// glues the points of a child mesh onto a parent mesh, so the child follows the parent when it
+// deforms without a rig of its own. every child point is stored as barycentric weights of the
+// parent triangle under it, or relative to the closest parent edge if it is outside.
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BoundPoint {
+    // inside of the parent, as weights of the points of the triangle it is in
+    Triangle {
+        triangle: TriangleId,
+        points: [PointId; 3],
+        weights: [f32; 3],
+    },
+    // outside of the parent, held by the closest edge so it moves, turns and scales with it.
+    // `along` goes from points[0] to points[1] and `across` to the left of it, both relative to
+    // the length of the edge
+    Edge {
+        edge: EdgeId,
+        points: [PointId; 2],
+        along: f32,
+        across: f32,
+    },
+}
+
+impl BoundPoint {
+    // where the point is, given where the parent points are
+    pub fn position(&self, parent: impl Fn(PointId) -> Option<Point2d>) -> Option<Point2d> {
+        match self {
+            BoundPoint::Triangle {
+                points, weights, ..
+            } => points
+                .iter()
+                .zip(weights)
+                .try_fold(Point2d::default(), |position, (point, weight)| {
+                    Some(position + parent(*point)? * *weight)
+                }),
+            BoundPoint::Edge {
+                points,
+                along,
+                across,
+                ..
+            } => {
+                let (a, b) = (parent(points[0])?, parent(points[1])?);
+                let direction = b - a;
+                let left = Point2d::new(-direction.y, direction.x);
+                Some(a + direction * *along + left * *across)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MeshBinding {
+    bound: SecondaryMap<PointId, BoundPoint>,
+}
+
+impl MeshBinding {
+    // binds every point of the child to the parent triangle it is in, or the closest edge
+    /// # Errors
+    ///
+    /// `Nari2DCoreError::DoesNotExist` or `Nari2DCoreError::NonManifoldStructure` for the first
+    /// point of the child that can't be bound, see `MeshBinding::bind_point`.
+    pub fn bind(parent: &EditMesh, child: &EditMesh) -> NCResult<MeshBinding> {
+        let mut bound = SecondaryMap::new();
+        for (id, point) in child.points() {
+            bound.insert(id, Self::bind_point(parent, *point)?);
+        }
+        Ok(MeshBinding { bound })
+    }
+
+    /// # Errors
+    ///
+    /// - `Nari2DCoreError::DoesNotExist` if the parent has no triangle or edge to bind to.
+    /// - `Nari2DCoreError::NonManifoldStructure` if the closest edge is missing a point or has no
+    ///   length.
+    pub fn bind_point(parent: &EditMesh, point: Point2d) -> NCResult<BoundPoint> {
+        if let Some(hit) = parent.pick_triangle(point) {
+            let points = parent
+                .triangle(hit.triangle)
+                .ok_or_else(|| {
+                    Nari2DCoreError::DoesNotExist(format!("Triangle {}", hit.triangle).into())
+                })?
+                .points();
+            return Ok(BoundPoint::Triangle {
+                triangle: hit.triangle,
+                points,
+                weights: hit.barycentric,
+            });
+        }
+
+        // extending the closest triangle instead would give huge weights for thin ones
+        let edge = parent
+            .pick_edge(point, f32::INFINITY)
+            .ok_or_else(|| Nari2DCoreError::DoesNotExist("Edge to bind to".into()))?
+            .edge;
+        let real_edge = parent
+            .edge(edge)
+            .ok_or_else(|| Nari2DCoreError::DoesNotExist(format!("Edge {edge}").into()))?;
+        let (Some(a), Some(b)) = (
+            parent.point(real_edge.point0),
+            parent.point(real_edge.point1),
+        ) else {
+            return Err(Nari2DCoreError::NonManifoldStructure);
+        };
+
+        let direction = *b - *a;
+        let length2 = direction.x * direction.x + direction.y * direction.y;
+        if length2 == 0_f32 {
+            return Err(Nari2DCoreError::NonManifoldStructure);
+        }
+        let offset = point - *a;
+        Ok(BoundPoint::Edge {
+            edge,
+            points: [real_edge.point0, real_edge.point1],
+            along: (offset.x * direction.x + offset.y * direction.y) / length2,
+            across: (direction.x * offset.y - direction.y * offset.x) / length2,
+        })
+    }
+
+    #[must_use]
+    pub fn bound_point(&self, child: PointId) -> Option<&BoundPoint> {
+        self.bound.get(child)
+    }
+
+    pub fn bound_points(&self) -> impl Iterator<Item = (PointId, &BoundPoint)> {
+        self.bound.iter()
+    }
+
+    pub fn unbind(&mut self, child: PointId) -> Option<BoundPoint> {
+        self.bound.remove(child)
+    }
+
+    // where the child points are on a deformed copy of the parent with the same ids
+    #[must_use]
+    pub fn deform(&self, parent: &EditMesh) -> SecondaryMap<PointId, Point2d> {
+        self.deform_with(|id| parent.point(id).copied())
+    }
+
+    // where the child points are, given where every parent point is. child points bound to a
+    // parent point that no longer has a position are left out.
+    pub fn deform_with(
+        &self,
+        parent: impl Fn(PointId) -> Option<Point2d>,
+    ) -> SecondaryMap<PointId, Point2d> {
+        self.bound
+            .iter()
+            .filter_map(|(child, bound)| Some((child, bound.position(&parent)?)))
+            .collect()
+    }
+}
@@ -1,21 +1,205 @@
 use crate::geometry::orientation::Orientation;
 use crate::{
-    error::{NCResult, Nari2DCoreError},
+    error::{MeshViolation, NCResult, Nari2DCoreError},
     geometry::{
         angle::Angle,
-        mesh::{Edge, EdgeId, PointEdge, PointId, Triangle, TriangleEdge, TriangleId},
+        bounds::Bounds,
+        mesh::{
+            journal::{Change, Remap},
+            simple_mesh::SimpleMesh,
+            Edge, EdgeId, MeshImplementation, PointEdge, PointId, Triangle, TriangleEdge,
+            TriangleId,
+        },
         point2d::Point2d,
+        polygon,
     },
 };
 use itertools::Itertools;
-use nanorand::{Rng, WyRand};
-use rstar::{primitives::GeomWithData, DefaultParams, RTree};
+use rstar::{primitives::GeomWithData, RTree};
 use slotmap::{SecondaryMap, SlotMap};
 use smallvec::smallvec;
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// where a point is relative to the triangles of the mesh, see `EditMesh::locate`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Located {
+    InTriangle(TriangleId),
+    OnEdge(EdgeId),
+    OnVertex(PointId),
+    Outside,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RefineOptions {
+    // Ruppert's algorithm is only guaranteed to finish below ~20.7 degrees
+    pub min_angle: Angle,
+    pub max_area: Option<f32>,
+    pub max_steiner_points: usize,
+}
+
+impl Default for RefineOptions {
+    fn default() -> Self {
+        RefineOptions {
+            min_angle: Angle::new(20_f32.to_radians()),
+            max_area: None,
+            max_steiner_points: 10_000,
+        }
+    }
+}
+
+impl RefineOptions {
+    // how far the triangle is from the targets, None if it meets them
+    fn badness(&self, min_angle: Angle, area: f32) -> Option<f32> {
+        let angle_badness = self.min_angle.0 - min_angle.0;
+        let area_badness = self.max_area.map_or(0_f32, |max| area / max - 1_f32);
+
+        if angle_badness > 0_f32 || area_badness > 0_f32 {
+            Some(angle_badness.max(0_f32) + area_badness.max(0_f32))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RefineReport {
+    pub points_added: usize,
+    // triangles still below the targets when refinement stopped
+    pub unfixed: Vec<TriangleId>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SmoothingMethod {
+    // every neighbour pulls equally
+    Laplacian,
+    // the centroids of the triangles around the point, weighted by their area, so uneven meshes
+    // are not pulled towards their denser side
+    AreaWeighted,
+    // moves points to the centroid of their voronoi cell
+    Lloyd,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SmoothOptions {
+    pub method: SmoothingMethod,
+    pub iterations: usize,
+    // how far a point moves towards its target every iteration, from 0 to 1
+    pub strength: f32,
+}
+
+impl Default for SmoothOptions {
+    fn default() -> Self {
+        SmoothOptions {
+            method: SmoothingMethod::Laplacian,
+            iterations: 8,
+            strength: 0.5,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DecimateOptions {
+    // stops once at most this many points are left
+    pub target_points: usize,
+    // stops before a collapse would move a point further than this
+    pub max_error: Option<f32>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Decimation {
+    // every point that was collapsed away, to the point that is left in its place
+    pub remap: SecondaryMap<PointId, PointId>,
+    // the furthest a single collapse moved a point
+    pub error: f32,
+}
+
+impl Decimation {
+    // the point that `id` ended up as, which is `id` itself if it was not collapsed
+    #[must_use]
+    pub fn resolve(&self, id: PointId) -> PointId {
+        self.remap.get(id).copied().unwrap_or(id)
+    }
+
+    // moves per point weights, like the influence of a bone, onto the points that are left.
+    // every point that is left gets the average of the weights of the points merged into it.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn remap_weights(
+        &self,
+        weights: &SecondaryMap<PointId, f32>,
+    ) -> SecondaryMap<PointId, f32> {
+        let mut merged: SecondaryMap<PointId, (f32, usize)> = SecondaryMap::new();
+        for (point, weight) in weights {
+            if let Some(entry) = merged.entry(self.resolve(point)) {
+                let (sum, count) = entry.or_insert((0_f32, 0));
+                *sum += *weight;
+                *count += 1;
+            }
+        }
+        merged
+            .into_iter()
+            .map(|(point, (sum, count))| (point, sum / count as f32))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConcaveHull {
+    // counter clockwise loop around every point
+    pub points: Vec<PointId>,
+    // the mesh edge for each side, points[i] to points[i + 1], if there is one
+    pub edges: Vec<Option<EdgeId>>,
+    // the k that the hull was found with
+    pub k: usize,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LoopKind {
+    Outer,
+    Hole,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoarderLoop {
+    // ordered with the mesh on the left, so outer loops are counter clockwise and holes clockwise
+    pub points: Vec<PointId>,
+    // edges[i] goes from points[i] to points[i + 1]
+    pub edges: Vec<EdgeId>,
+    pub kind: LoopKind,
+    pub signed_area: f32,
+}
+
+// a connected component copied out into its own mesh
+#[derive(Clone, Debug)]
+pub struct SubMesh {
+    pub mesh: EditMesh,
+    // from the ids of the original mesh to the ids in `mesh`
+    pub points: SecondaryMap<PointId, PointId>,
+}
+
+// only what can't be rebuilt from the triangles, for saving meshes without their connectivity.
+// ids are not kept, points are indexed in the order of `EditMesh::points`
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde_impl", derive(Serialize, Deserialize))]
+pub struct CompactMesh {
+    pub points: Vec<Point2d>,
+    // same length as points, None for points without a uv
+    pub uvs: Vec<Option<Point2d>>,
+    // counter clockwise
+    pub triangles: Vec<[u32; 3]>,
+    pub locked_edges: Vec<[u32; 2]>,
+    pub holes: Vec<Point2d>,
+    pub carve_exterior: bool,
+}
 
 // based off of https://www.gradientspace.com/tutorials/dmesh3
+// serializes with the slotmap key versions, so ids saved elsewhere still resolve after loading
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(
+    feature = "serde_impl",
+    derive(Serialize, Deserialize),
+    serde(from = "StoredEditMesh")
+)]
 pub struct EditMesh {
     points: SlotMap<PointId, Point2d>,
     triangles: SlotMap<TriangleId, Triangle>,
@@ -23,6 +207,58 @@ pub struct EditMesh {
     point_edges: SecondaryMap<PointId, PointEdge>,
     triangle_edges: SecondaryMap<TriangleId, TriangleEdge>,
     boarder_edges: Vec<EdgeId>,
+    holes: Vec<Point2d>,
+    carve_exterior: bool,
+    // every point, kept in sync with `points` so nearest point queries don't scan the mesh
+    #[cfg_attr(feature = "serde_impl", serde(skip))]
+    index: RTree<GeomWithData<Point2d, PointId>>,
+    // texture coordinates, (0, 0) being the top left of the texture. points without one are
+    // not textured
+    uvs: SecondaryMap<PointId, Point2d>,
+    // every change made while an EditJournal is recording, see `journal`
+    #[cfg_attr(feature = "serde_impl", serde(skip))]
+    changes: Option<Vec<Change>>,
+}
+
+// EditMesh without the index, which is rebuilt from the points on load
+#[cfg(feature = "serde_impl")]
+#[derive(Deserialize)]
+struct StoredEditMesh {
+    points: SlotMap<PointId, Point2d>,
+    triangles: SlotMap<TriangleId, Triangle>,
+    edges: SlotMap<EdgeId, Edge>,
+    point_edges: SecondaryMap<PointId, PointEdge>,
+    triangle_edges: SecondaryMap<TriangleId, TriangleEdge>,
+    boarder_edges: Vec<EdgeId>,
+    holes: Vec<Point2d>,
+    carve_exterior: bool,
+    uvs: SecondaryMap<PointId, Point2d>,
+}
+
+#[cfg(feature = "serde_impl")]
+impl From<StoredEditMesh> for EditMesh {
+    fn from(stored: StoredEditMesh) -> Self {
+        let index = RTree::bulk_load(
+            stored
+                .points
+                .iter()
+                .map(|(id, point)| GeomWithData::new(*point, id))
+                .collect(),
+        );
+        EditMesh {
+            points: stored.points,
+            triangles: stored.triangles,
+            edges: stored.edges,
+            point_edges: stored.point_edges,
+            triangle_edges: stored.triangle_edges,
+            boarder_edges: stored.boarder_edges,
+            holes: stored.holes,
+            carve_exterior: stored.carve_exterior,
+            index,
+            uvs: stored.uvs,
+            changes: None,
+        }
+    }
 }
 
 impl EditMesh {
@@ -50,6 +286,77 @@ impl EditMesh {
         self.triangle_edges.get(id)
     }
 
+    pub fn points(&self) -> impl Iterator<Item = (PointId, &Point2d)> {
+        self.points.iter()
+    }
+
+    pub fn triangles(&self) -> impl Iterator<Item = (TriangleId, &Triangle)> {
+        self.triangles.iter()
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = (EdgeId, &Edge)> {
+        self.edges.iter()
+    }
+
+    #[must_use]
+    pub fn uv(&self, id: PointId) -> Option<&Point2d> {
+        self.uvs.get(id)
+    }
+
+    pub fn uvs(&self) -> impl Iterator<Item = (PointId, &Point2d)> {
+        self.uvs.iter()
+    }
+
+    /// # Errors
+    ///
+    /// `Nari2DCoreError::DoesNotExist` if the point is missing.
+    pub fn set_uv(&mut self, id: PointId, uv: Point2d) -> NCResult<()> {
+        if !self.points.contains_key(id) {
+            return Err(Nari2DCoreError::DoesNotExist(format!("Point {id}").into()));
+        }
+        self.put_uv(id, Some(uv));
+        Ok(())
+    }
+
+    // sets or clears the uv of a point
+    fn put_uv(&mut self, id: PointId, uv: Option<Point2d>) {
+        let from = match uv {
+            Some(uv) => self.uvs.insert(id, uv),
+            None => self.uvs.remove(id),
+        };
+        self.log(Change::SetUv { id, from });
+    }
+
+    // maps the texture onto `reference`, with its min corner being uv (0, 0) and its max corner
+    // uv (1, 1). points outside of it get uvs outside of 0 to 1.
+    pub fn generate_uvs(&mut self, reference: Bounds) {
+        let (min, size) = (reference.min(), reference.size());
+        let scale = |value: f32, min: f32, size: f32| {
+            if size == 0_f32 {
+                0_f32
+            } else {
+                (value - min) / size
+            }
+        };
+
+        let uvs = self
+            .points
+            .iter()
+            .map(|(id, point)| {
+                let uv = Point2d::new(scale(point.x, min.x, size.x), scale(point.y, min.y, size.y));
+                (id, uv)
+            })
+            .collect_vec();
+        for (id, uv) in uvs {
+            self.put_uv(id, Some(uv));
+        }
+    }
+
+    #[must_use]
+    pub fn boarder_edges(&self) -> &[EdgeId] {
+        &self.boarder_edges
+    }
+
     pub fn edge_from_points(&self, a: PointId, b: PointId) -> Option<&EdgeId> {
         let a_edges = self.point_edges.get(a)?;
         let b_edges = self.point_edges.get(b)?;
@@ -65,287 +372,273 @@ impl EditMesh {
         None
     }
 
+    // freezes the mesh into its runtime form
+    #[must_use]
+    pub fn compile(&self) -> SimpleMesh {
+        SimpleMesh::from(self)
+    }
+
     pub fn insert_point(&mut self, point: Point2d) -> PointId {
-        self.points.insert(point)
+        let id = self.points.insert(point);
+        self.index.insert(GeomWithData::new(point, id));
+        self.log(Change::InsertPoint(id));
+        id
     }
 
-    pub fn remove_point(&mut self, id: PointId) -> Option<Point2d> {
-        self.points.remove(id)
+    // removes the point from `points` and the index, the connectivity is left to the caller
+    fn forget_point(&mut self, id: PointId) -> Option<Point2d> {
+        let position = self.points.remove(id)?;
+        self.index.remove(&GeomWithData::new(position, id));
+        let uv = self.uvs.remove(id);
+        self.log(Change::RemovePoint { id, position, uv });
+        Some(position)
     }
 
-    pub fn insert_point_with_connectivity(&mut self, point: Point2d) -> Option<PointId> {
-        // first, check thing
-        // first, we need to determine if the point lies outside or inside.
-        match self.point_inside_polygon(&point) {
-            true => self.insert_point_inside(point),
-            false => self.insert_point_outside(point),
-        }
-    }
-
-    #[allow(clippy::similar_names)]
-    fn insert_point_outside(&mut self, point: Point2d) -> Option<PointId> {
-        // get nearest 2 points
-        let nearest_2 = self.closest_n(&point, 2)?;
-        let pt_a = *nearest_2.get(0)?;
-        let pt_b = *nearest_2.get(1)?;
-        let a_b_edge = *self.edge_from_points(pt_a, pt_b)?; // this is the edge we will delete from the hull
-        let new_pt = self.insert_point(point);
-        // find the ccw triangle for this
-        // make sure they are not colinear
-        let real_pt_a = self.points.get(pt_a)?;
-        let real_pt_b = self.points.get(pt_b)?;
-        let real_pt_c = self.points.get(new_pt)?;
-        let sorted_triangle: Triangle = match Point2d::orientation(real_pt_a, real_pt_b, real_pt_c)
-        {
-            Orientation::CounterClockWise => [pt_a, pt_b, new_pt].into(),
-            Orientation::ClockWise => [pt_a, new_pt, pt_b].into(),
-            Orientation::Colinear => {
-                self.remove_point(new_pt);
-                return None;
+    // inserts a point at where it was located, giving it the uv the texture has there
+    fn insert_interpolated(&mut self, point: Point2d, located: Located) -> PointId {
+        let uv = self.interpolate_uv(point, located);
+        let id = self.insert_point(point);
+        if uv.is_some() {
+            self.put_uv(id, uv);
+        }
+        id
+    }
+
+    // the texture is affine over each triangle, outside of the mesh the nearest one is extended
+    fn interpolate_uv(&self, point: Point2d, located: Located) -> Option<Point2d> {
+        let triangle = match located {
+            Located::InTriangle(triangle) => triangle,
+            Located::OnEdge(edge) => {
+                let edge = self.edges.get(edge)?;
+                edge.triangle0.or(edge.triangle1)?
             }
+            Located::OnVertex(vertex) => return self.uvs.get(vertex).copied(),
+            Located::Outside => self.nearest_triangle(point)?,
         };
 
-        let triangleid = self.insert_triangle(sorted_triangle);
-        // insert the 2 new edges as well
-        let edge_new_a = Edge {
-            point0: pt_a,
-            point1: new_pt,
-            triangle0: Some(triangleid),
-            triangle1: None,
-        };
-        let edge_new_b = Edge {
-            point0: pt_b,
-            point1: new_pt,
-            triangle0: Some(triangleid),
-            triangle1: None,
-        };
-        let edge_npt_a_id = self.insert_edge(edge_new_a);
-        let edge_npt_b_id = self.insert_edge(edge_new_b);
-        // update the existing edge
-        self.edges
-            .get_mut(a_b_edge)?
-            .add_triangle_unoccupied(triangleid)?;
-        // update point edges and triangle edges
-        self.triangle_edges.insert(
-            triangleid,
-            TriangleEdge {
-                edges: [edge_npt_a_id, edge_npt_b_id, a_b_edge],
-            },
-        );
-        self.add_pointedge_or_init(pt_a, edge_npt_a_id);
-        self.add_pointedge_or_init(pt_b, edge_npt_b_id);
-        self.add_pointedge_or_init(new_pt, edge_npt_a_id);
-        self.add_pointedge_or_init(new_pt, edge_npt_b_id);
-        self.add_pointedge_or_init(pt_a, a_b_edge); // just making sure
-        self.add_pointedge_or_init(pt_b, a_b_edge); // just making sure
-                                                    // update our hull ==> remove a_b_edge, in its place put our 2 new edges
-        self.boarder_edges.retain(|e| e != &a_b_edge);
-        self.boarder_edges.push(edge_npt_a_id);
-        self.boarder_edges.push(edge_npt_b_id);
-        Some(new_pt)
-    }
-
-    #[allow(clippy::similar_names)]
-    fn insert_point_inside(&mut self, point: Point2d) -> Option<PointId> {
-        struct OldEdges {
-            p0p1: EdgeId,
-            p1p2: EdgeId,
-            p2p0: EdgeId,
-        }
-
-        // find the nearest 2 points, and find the triangle that encloses this point.
-        let nearest_2 = self.closest_n(&point, 2)?;
-        let pt_a = *nearest_2.get(0)?;
-        let pt_b = *nearest_2.get(1)?;
-        let a_b_edge = *self.edge_from_points(pt_a, pt_b)?;
-        let new_pt = self.insert_point(point);
-        // find the triangle
-        let real_edge_of_ab = self.edges.get(a_b_edge)?;
-        let canidate_a = real_edge_of_ab.triangle0;
-        let canidate_b = real_edge_of_ab.triangle1;
-        // todo: let chains?
-        let encasing_triangle_id = match (canidate_a, canidate_b) {
-            // I LOVE RUST MATCHES I LOVE RUST MATCHES I LOVE THE ANTICHRIST AAAAAAAAAAAAAAAA
-            (Some(c_a), Some(c_b)) => {
-                let triangle_a = self.triangle(c_a)?;
-                let t_a_p0 = *self.points.get(triangle_a.point0)?;
-                let t_a_p1 = *self.points.get(triangle_a.point1)?;
-                let t_a_p2 = *self.points.get(triangle_a.point2)?;
-                let triangle_b = self.triangle(c_a)?;
-                let t_b_p0 = *self.points.get(triangle_b.point0)?;
-                let t_b_p1 = *self.points.get(triangle_b.point1)?;
-                let t_b_p2 = *self.points.get(triangle_b.point2)?;
-                if point.is_inside(&[t_a_p0, t_a_p1, t_a_p2]) {
-                    c_a
-                } else if point.is_inside(&[t_b_p0, t_b_p1, t_b_p2]) {
-                    c_b
-                } else {
-                    return None;
-                }
-            }
-            (_, Some(c_b)) => {
-                let triangle_b = self.triangle(c_b)?;
-                let t_b_p0 = *self.points.get(triangle_b.point0)?;
-                let t_b_p1 = *self.points.get(triangle_b.point1)?;
-                let t_b_p2 = *self.points.get(triangle_b.point2)?;
-                if point.is_inside(&[t_b_p0, t_b_p1, t_b_p2]) {
-                    c_b
-                } else {
-                    return None;
-                }
+        let real_triangle = self.triangles.get(triangle)?;
+        let [p0, p1, p2] = self.triangle_positions(real_triangle)?;
+        let weights = point.barycentric(&p0, &p1, &p2)?;
+        real_triangle
+            .points()
+            .into_iter()
+            .zip(weights)
+            .try_fold(Point2d::default(), |uv, (vertex, weight)| {
+                Some(uv + *self.uvs.get(vertex)? * weight)
+            })
+    }
+
+    fn move_point(&mut self, id: PointId, to: Point2d) {
+        if let Some(point) = self.points.get_mut(id) {
+            let from = std::mem::replace(point, to);
+            self.index.remove(&GeomWithData::new(from, id));
+            self.index.insert(GeomWithData::new(to, id));
+            self.log(Change::MovePoint { id, from });
+        }
+    }
+
+    // removes the point with its triangles and fills the hole left behind, keeping delaunay.
+    // if the point was on the boarder, the boarder is moved inwards.
+    /// # Errors
+    ///
+    /// The mesh is left as it was.
+    ///
+    /// - `Nari2DCoreError::DoesNotExist` if the point is missing.
+    /// - `Nari2DCoreError::NonManifoldStructure` if more than one fan of triangles meets at the
+    ///   point.
+    /// - `Nari2DCoreError::Locked` if the point ends a constraint, or more than two meet at it.
+    /// - `Nari2DCoreError::Triangulation` or `Nari2DCoreError::ThisIsABug` if a constraint through
+    ///   the point can't be put back.
+    pub fn remove_point(&mut self, id: PointId) -> NCResult<Point2d> {
+        let position = *self
+            .points
+            .get(id)
+            .ok_or_else(|| Nari2DCoreError::DoesNotExist(format!("Point {id}").into()))?;
+
+        let around = self.triangles_around(id);
+        if around.is_empty() {
+            self.point_edges.remove(id);
+            return self
+                .forget_point(id)
+                .ok_or(Nari2DCoreError::ThisIsABug("Point should exist!".into()));
+        }
+
+        // every triangle around the point, as the edge facing away from it
+        let mut next = SecondaryMap::with_capacity(around.len());
+        for triangle in &around {
+            let rotated = self
+                .triangles
+                .get(*triangle)
+                .and_then(|tri| {
+                    let [p0, p1, p2] = tri.points();
+                    if p0 == id {
+                        Some((p1, p2))
+                    } else if p1 == id {
+                        Some((p2, p0))
+                    } else if p2 == id {
+                        Some((p0, p1))
+                    } else {
+                        None
+                    }
+                })
+                .ok_or(Nari2DCoreError::NonManifoldStructure)?;
+            next.insert(rotated.0, rotated.1);
+        }
+
+        // a point on the boarder has a fan that does not close, start at its open end
+        let start = next
+            .keys()
+            .find(|pt| !next.values().any(|to| to == pt))
+            .or_else(|| next.keys().next())
+            .ok_or(Nari2DCoreError::NonManifoldStructure)?;
+        let closed = next.values().all(|to| next.contains_key(*to));
+        let mut chain = vec![start];
+        while let Some(to) = next.get(*chain.last().unwrap_or(&start)) {
+            if *to == start {
+                break;
             }
-            (Some(c_a), _) => {
-                let triangle_a = self.triangle(c_a)?;
-                let t_a_p0 = *self.points.get(triangle_a.point0)?;
-                let t_a_p1 = *self.points.get(triangle_a.point1)?;
-                let t_a_p2 = *self.points.get(triangle_a.point2)?;
-                if point.is_inside(&[t_a_p0, t_a_p1, t_a_p2]) {
-                    c_a
-                } else {
-                    return None;
-                }
+            chain.push(*to);
+            if chain.len() > next.len() + 1 {
+                return Err(Nari2DCoreError::NonManifoldStructure);
             }
-            (_, _) => return None,
-        };
-        let encasing_tri = self.triangles.get(encasing_triangle_id)?;
-        let ec_tri_p0 = encasing_tri.point0;
-        let valof_ec_t_p0 = self.points.get(ec_tri_p0)?;
-        let ec_tri_p1 = encasing_tri.point1;
-        let valof_ec_t_p1 = self.points.get(ec_tri_p1)?;
-        let ec_tri_p2 = encasing_tri.point2;
-        let valof_ec_t_p2 = self.points.get(ec_tri_p2)?;
-
-        // now, we delete this triangle, and create 3 new triangles.
-        // this means we must delete the triangle, and triangle edges
-        // edges and point edges stay the same.
-        let old_edges = self.triangle_edges.remove(encasing_triangle_id)?;
-        // figure out what the fuck these edges are
-
-        let (old_edge_p0p1, old_edge_p1p2, old_edge_p2p0) = {
-            let mut olds = OldEdges {
-                p0p1: Default::default(),
-                p1p2: Default::default(),
-                p2p0: Default::default(),
-            };
+        }
+        if chain.len() != next.len() + usize::from(!closed) {
+            // more than one fan meets at this point
+            return Err(Nari2DCoreError::NonManifoldStructure);
+        }
 
-            for edge in old_edges.edges {
-                let redge = self.edges.get(edge)?;
-                if redge.contains_point(ec_tri_p0) && redge.contains_point(ec_tri_p1) {
-                    olds.p0p1 = edge;
-                } else if redge.contains_point(ec_tri_p1) && redge.contains_point(ec_tri_p2) {
-                    olds.p1p2 = edge;
-                } else {
-                    olds.p2p0 = edge;
+        let locked = self
+            .point_edges
+            .get(id)
+            .map(|pe| {
+                pe.edges
+                    .iter()
+                    .filter_map(|e| self.edges.get(*e))
+                    .filter(|e| e.locked)
+                    .filter_map(|e| e.other_point(id))
+                    .collect_vec()
+            })
+            .unwrap_or_default();
+        // only a point in the middle of a constraint can be removed without losing part of it
+        if !matches!(locked.len(), 0 | 2) {
+            return Err(Nari2DCoreError::Locked(format!("Point {id}").into()));
+        }
+
+        self.or_revert(|mesh| {
+            let mut old_edges = vec![];
+            for triangle in around {
+                if let Some((_, edges)) = mesh.disconnect_triangle(triangle) {
+                    old_edges.extend(edges.edges);
                 }
             }
-            (olds.p0p1, olds.p1p2, olds.p2p0)
-        };
-        self.triangles.remove(encasing_triangle_id);
-        // start insert triangles
-        let tri_p0_p1 = match Point2d::orientation(valof_ec_t_p0, &point, valof_ec_t_p1) {
-            Orientation::CounterClockWise => Triangle {
-                point0: ec_tri_p0,
-                point1: new_pt,
-                point2: ec_tri_p1,
-            },
-            Orientation::ClockWise => Triangle {
-                point0: ec_tri_p0,
-                point1: ec_tri_p1,
-                point2: new_pt,
-            },
-            Orientation::Colinear => {
-                self.remove_point(new_pt);
-                return None;
-            }
-        };
-        let tri_p1_p2 = match Point2d::orientation(valof_ec_t_p1, &point, valof_ec_t_p2) {
-            Orientation::CounterClockWise => Triangle {
-                point0: ec_tri_p1,
-                point1: new_pt,
-                point2: ec_tri_p2,
-            },
-            Orientation::ClockWise => Triangle {
-                point0: ec_tri_p1,
-                point1: ec_tri_p2,
-                point2: new_pt,
-            },
-            Orientation::Colinear => {
-                self.remove_point(new_pt);
-                return None;
+
+            let filled = mesh.fill_star(position, chain, closed)?;
+            mesh.prune_edges(old_edges);
+            mesh.legalize(filled);
+
+            // a point in the middle of a constraint leaves it whole
+            if let [a, b] = locked[..] {
+                mesh.insert_constraint(a, b)?;
             }
-        };
-        let tri_p2_p0 = match Point2d::orientation(valof_ec_t_p2, &point, valof_ec_t_p0) {
-            Orientation::CounterClockWise => Triangle {
-                point0: ec_tri_p2,
-                point1: new_pt,
-                point2: ec_tri_p0,
-            },
-            Orientation::ClockWise => Triangle {
-                point0: ec_tri_p2,
-                point1: ec_tri_p0,
-                point2: new_pt,
-            },
-            Orientation::Colinear => {
-                self.remove_point(new_pt);
-                return None;
+            Ok(())
+        })?;
+
+        // nothing is connected to it anymore, so this can't fail halfway
+        self.point_edges.remove(id);
+        self.forget_point(id);
+        Ok(position)
+    }
+
+    // https://doi.org/10.1142/S0218195902000773 (Devillers, delaunay ears)
+    // triangulates the hole left by a removed point at `center`, using only ears that lie in its
+    // old star. returns the new edges.
+    fn fill_star(
+        &mut self,
+        center: Point2d,
+        mut chain: Vec<PointId>,
+        closed: bool,
+    ) -> NCResult<Vec<(PointId, PointId)>> {
+        let mut new_edges = vec![];
+
+        while chain.len() > if closed { 3 } else { 2 } {
+            let ears = (0..chain.len())
+                .filter(|idx| closed || (*idx != 0 && *idx != chain.len() - 1))
+                .filter_map(|idx| {
+                    let prev = chain[(idx + chain.len() - 1) % chain.len()];
+                    let next = chain[(idx + 1) % chain.len()];
+                    let positions = [
+                        *self.points.get(prev)?,
+                        *self.points.get(chain[idx])?,
+                        *self.points.get(next)?,
+                    ];
+                    let convex = Point2d::orientation(&positions[0], &positions[1], &positions[2])
+                        .is_counter_clock_wise()
+                        && !Point2d::orientation(&center, &positions[0], &positions[2])
+                            .is_clockwise();
+                    let existing = self.edge_from_points(prev, next).is_some();
+                    (convex && !existing).then_some((idx, positions))
+                })
+                .collect_vec();
+
+            let delaunay_ear = ears.iter().find(|(idx, [p0, p1, p2])| {
+                chain
+                    .iter()
+                    .enumerate()
+                    .filter(|(other, _)| {
+                        *other != *idx
+                            && *other != (idx + 1) % chain.len()
+                            && *other != (idx + chain.len() - 1) % chain.len()
+                    })
+                    .filter_map(|(_, pt)| self.points.get(*pt))
+                    .all(|pt| !pt.in_circumcircle(p0, p1, p2))
+            });
+
+            let Some((idx, _)) = delaunay_ear.or_else(|| ears.first()) else {
+                // what is left is outside of the star, it becomes boarder
+                break;
+            };
+            let idx = *idx;
+            let prev = chain[(idx + chain.len() - 1) % chain.len()];
+            let next = chain[(idx + 1) % chain.len()];
+
+            self.connect_triangle([prev, chain[idx], next].into())?;
+            new_edges.extend([(prev, chain[idx]), (chain[idx], next), (next, prev)]);
+            chain.remove(idx);
+        }
+
+        if closed {
+            if let [p0, p1, p2] = chain[..] {
+                self.connect_triangle([p0, p1, p2].into())?;
+                new_edges.extend([(p0, p1), (p1, p2), (p2, p0)]);
             }
-        };
-        let id_npt_p0_p1 = self.insert_triangle(tri_p0_p1);
-        let id_npt_p1_p2 = self.insert_triangle(tri_p1_p2);
-        let id_npt_p2_p0 = self.insert_triangle(tri_p2_p0);
-        // find out the edges for these triangles
-        // insert the new edges
-        let edge_npt_p0 = Edge {
-            point0: new_pt,
-            point1: ec_tri_p0,
-            triangle0: Some(id_npt_p0_p1),
-            triangle1: Some(id_npt_p2_p0),
-        };
-        let edge_npt_p1 = Edge {
-            point0: new_pt,
-            point1: ec_tri_p1,
-            triangle0: Some(id_npt_p1_p2),
-            triangle1: Some(id_npt_p0_p1),
-        };
-        let edge_npt_p2 = Edge {
-            point0: new_pt,
-            point1: ec_tri_p2,
-            triangle0: Some(id_npt_p2_p0),
-            triangle1: Some(id_npt_p1_p2),
-        };
-        let npt_p0_id = self.insert_edge(edge_npt_p0);
-        let npt_p1_id = self.insert_edge(edge_npt_p1);
-        let npt_p2_id = self.insert_edge(edge_npt_p2);
+        }
 
-        let edges_of_t_p0p1 = TriangleEdge {
-            edges: [old_edge_p0p1, npt_p0_id, npt_p1_id],
-        };
-        let edges_of_t_p1p2 = TriangleEdge {
-            edges: [old_edge_p1p2, npt_p1_id, npt_p2_id],
-        };
-        let edges_of_t_p2p0 = TriangleEdge {
-            edges: [old_edge_p2p0, npt_p2_id, npt_p0_id],
-        };
-        self.triangle_edges.insert(id_npt_p0_p1, edges_of_t_p0p1);
-        self.triangle_edges.insert(id_npt_p1_p2, edges_of_t_p1p2);
-        self.triangle_edges.insert(id_npt_p2_p0, edges_of_t_p2p0);
-        // update pointedges
-        self.add_pointedge_or_init(ec_tri_p0, npt_p0_id);
-        self.add_pointedge_or_init(ec_tri_p1, npt_p1_id);
-        self.add_pointedge_or_init(ec_tri_p2, npt_p2_id);
-        self.add_pointedge_or_init(new_pt, npt_p0_id);
-        self.add_pointedge_or_init(new_pt, npt_p1_id);
-        self.add_pointedge_or_init(new_pt, npt_p2_id);
+        Ok(new_edges)
+    }
 
-        Some(new_pt)
+    pub fn insert_point_with_connectivity(&mut self, point: Point2d) -> Option<PointId> {
+        match self.locate(point) {
+            Located::OnVertex(_) => None,
+            located => self
+                .or_revert(|mesh| {
+                    let id = mesh.insert_interpolated(point, located);
+                    mesh.triangulate_point(id, located)?;
+                    Ok(id)
+                })
+                .ok(),
+        }
     }
 
     pub fn insert_edge(&mut self, edge: Edge) -> EdgeId {
-        self.edges.insert(edge)
+        let id = self.edges.insert(edge);
+        self.log(Change::InsertEdge(id));
+        id
     }
 
     pub fn insert_triangle(&mut self, triangle: Triangle) -> TriangleId {
-        self.triangles.insert(triangle)
+        let id = self.triangles.insert(triangle);
+        self.log(Change::ConnectTriangle(id));
+        id
     }
 
     pub fn add_pointedge_or_init(&mut self, point: PointId, data: EdgeId) {
@@ -367,258 +660,2060 @@ impl EditMesh {
     }
 
     // https://repositorium.sdum.uminho.pt/bitstream/1822/6429/1/ConcaveHull_ACM_MYS.pdf
-    pub fn calculate_concave_hull(&self, nearest: i32) -> NCResult<Vec<EdgeId>> {
-        let how_many_nearest = nearest as usize;
-
-        if self.points.len() < how_many_nearest {
-            return Err(Nari2DCoreError::TooFewPoints(self.points.len() as u8));
-        }
-
-        let data = self
+    // https://doi.org/10.5220/0002080800610068 (Moreira, Santos)
+    // k nearest neighbours concave hull. k starts at `nearest` (at least 3) and is raised until
+    // the hull encloses every point, a higher k gives a smoother hull.
+    /// # Errors
+    ///
+    /// `Nari2DCoreError::TooFewPoints` with fewer than 3 points.
+    pub fn calculate_concave_hull(&self, nearest: usize) -> NCResult<ConcaveHull> {
+        let unique = self
             .points
             .iter()
-            .map(|(data, pt)| GeomWithData::new(*pt, data))
+            .sorted_by(|(_, a), (_, b)| a.cmp(b))
+            .dedup_by(|(_, a), (_, b)| a == b)
             .collect_vec();
-        let mut tree: RTree<GeomWithData<Point2d, PointId>, DefaultParams> =
-            RTree::bulk_load_with_params(data); // TODO: maybe try contributing a const rtree with borrowed data?
-        let mut hull = vec![];
-        let first_point = tree
-            .nearest_neighbor(&Point2d::new(0_f32, f32::NEG_INFINITY))
-            .ok_or(Nari2DCoreError::ThisIsABug(
-                "No neighbour - this is a bug! please report it!".into(),
-            ))?;
-        hull.push(first_point.data);
-
-        let mut current_point = first_point;
-
-        tree.remove_at_point(first_point.geom());
-
-        let mut previous_angle = Angle::new(0_f32);
-        let mut step = 2;
-
-        while (current_point != first_point || step == 2) && hull.len() < self.points.len() {
-            if step == 5 {
-                tree.insert(*first_point);
-            }
-            let nearest_points_by_angle = tree
-                .nearest_neighbor_iter(current_point.geom())
-                .take(how_many_nearest)
-                .sorted_by(|a, b| {
-                    // FIXME: I dont know what "right hand turn" is
-                    let mut subpt = *current_point.geom();
-                    subpt.y -= 1_f32;
-                    let a_angle = Angle::from_3_points(&subpt, current_point.geom(), a.geom());
-                    let b_angle = Angle::from_3_points(&subpt, current_point.geom(), b.geom());
-                    a_angle.cmp(&b_angle)
-                })
-                .collect_vec();
-
-            let mut its = true;
-            let mut index = 0;
+        if unique.len() < 3 {
+            return Err(Nari2DCoreError::TooFewPoints(
+                u8::try_from(unique.len()).unwrap_or(u8::MAX),
+            ));
+        }
 
-            while its && index < nearest_points_by_angle.len() {
-                index += 1;
-                let lastpoint = if nearest_points_by_angle.get(index) == Some(&first_point) {
-                    1
-                } else {
-                    0
-                };
+        for k in nearest.max(3)..unique.len() {
+            if let Some(points) = Self::concave_hull_with(&unique, k) {
+                let edges = points
+                    .iter()
+                    .circular_tuple_windows()
+                    .map(|(a, b)| self.edge_from_points(*a, *b).copied())
+                    .collect();
+                return Ok(ConcaveHull { points, edges, k });
+            }
+        }
 
-                let mut jdx_mutator = 2;
-                its = false;
-                while !its && jdx_mutator < (hull.len() - lastpoint) {
-                    let a = some_or_continue!(hull
-                        .get(step - 1)
-                        .map(|x| self.points.get(*x))
-                        .flatten());
-                    let b = some_or_continue!(nearest_points_by_angle.get(index)).geom();
-                    let c = some_or_continue!(hull
-                        .get(step - 1 - jdx_mutator)
-                        .map(|x| self.points.get(*x))
-                        .flatten());
-                    let d = some_or_continue!(hull
-                        .get(step - jdx_mutator)
-                        .map(|x| self.points.get(*x))
-                        .flatten());
-                    its = Point2d::line_intersect((a, b), (c, d));
-                    jdx_mutator += 1;
-                }
-            }
-
-            if its {
-                return Err(Nari2DCoreError::HullCalculation("k too small!".into()));
-            }
-
-            current_point = some_or_continue!(nearest_points_by_angle.get(index));
-            hull.push(current_point.data);
-
-            let h_step = some_or_continue!(hull.get(step).map(|x| self.points.get(*x)).flatten());
-            let h_step_n1 =
-                some_or_continue!(hull.get(step - 1).map(|x| self.points.get(*x)).flatten());
-            previous_angle = Angle::from_2_points(h_step, h_step_n1);
-            tree.remove(current_point);
-            step += 1;
-        }
-        let polygon = hull
+        // with every point as a neighbour this is the convex hull, which always works
+        let points = self.convex_hull();
+        let edges = points
             .iter()
-            .map(|x| self.points.get(*x).map(|x| *x))
-            .collect::<Option<Vec<Point2d>>>()
-            .ok_or(Nari2DCoreError::ThisIsABug("This should not error!".into()))?;
+            .circular_tuple_windows()
+            .map(|(a, b)| self.edge_from_points(*a, *b).copied())
+            .collect();
+        Ok(ConcaveHull {
+            points,
+            edges,
+            k: unique.len(),
+        })
+    }
+
+    // one attempt at a concave hull with a fixed k, None if k was too small
+    fn concave_hull_with(unique: &[(PointId, &Point2d)], k: usize) -> Option<Vec<PointId>> {
+        let mut remaining: RTree<GeomWithData<Point2d, PointId>> = RTree::bulk_load(
+            unique
+                .iter()
+                .map(|(id, point)| GeomWithData::new(**point, *id))
+                .collect(),
+        );
 
-        for point in tree.iter() {
-            // check points
-            if !point.geom().is_inside(&polygon) {
-                return Err(Nari2DCoreError::HullCalculation("k too small!".into()));
+        // the lowest point is always on the hull. pretend we came in from the left, so the first
+        // turn keeps every point on the left of the hull
+        let first = unique
+            .iter()
+            .min_by(|(_, a), (_, b)| f32::total_cmp(&a.y, &b.y).then(f32::total_cmp(&a.x, &b.x)))
+            .map(|(id, point)| GeomWithData::new(**point, *id))?;
+        remaining.remove(&first);
+
+        let mut hull = vec![first];
+        let mut current = first;
+        let mut back = Point2d::new(-1_f32, 0_f32);
+
+        while current != first || hull.len() == 1 {
+            // the first point can only close the hull once it is big enough to enclose something
+            if hull.len() == 4 {
+                remaining.insert(first);
             }
-        }
 
-        let mut previous = *hull.last().ok_or(Nari2DCoreError::ThisIsABug(
-            "Hull should not be empty".into(),
-        ))?;
-        let mut hull_edges = vec![];
+            // biggest right hand turn first, that is the one keeping the most points on the left
+            let candidates = remaining
+                .nearest_neighbor_iter(current.geom())
+                .take(k)
+                .copied()
+                .sorted_by(|a, b| {
+                    let turn_a = clockwise_angle(back, *a.geom() - *current.geom());
+                    let turn_b = clockwise_angle(back, *b.geom() - *current.geom());
+                    f32::total_cmp(&turn_b, &turn_a)
+                })
+                .collect_vec();
 
-        for pid in hull {
-            match self.edge_from_points(pid, previous) {
-                Some(eid) => hull_edges.push(*eid),
-                None => {
-                    return Err(Nari2DCoreError::DoesNotExist(
-                        "Edge for points does not exist, is the indexed mesh broken?".into(),
-                    ))
-                }
+            let next = candidates.into_iter().find(|candidate| {
+                let closing = *candidate == first;
+                // the new side may only touch the side before it, and the first side when closing
+                let skip_start = usize::from(closing);
+                let checked_sides = hull.len().saturating_sub(2);
+                !hull
+                    .iter()
+                    .tuple_windows()
+                    .take(checked_sides)
+                    .skip(skip_start)
+                    .any(|(a, b)| {
+                        Point2d::line_intersect(
+                            (current.geom(), candidate.geom()),
+                            (a.geom(), b.geom()),
+                        )
+                    })
+            })?;
+
+            back = *current.geom() - *next.geom();
+            current = next;
+            remaining.remove(&next);
+            if current != first {
+                hull.push(current);
             }
         }
 
-        Ok(hull_edges)
+        let polygon = hull.iter().map(|point| *point.geom()).collect_vec();
+        unique
+            .iter()
+            .all(|(_, point)| polygon::contains(&polygon, **point))
+            .then(|| hull.into_iter().map(|point| point.data).collect())
     }
 
-    pub fn recalculate_hull(&mut self, smoothness: i32) -> NCResult<()> {
+    // the concave hull becomes the boarder: it is added as constraints and everything outside
+    // of it is removed
+    /// # Errors
+    ///
+    /// - `Nari2DCoreError::TooFewPoints` with fewer than 3 points.
+    /// - `Nari2DCoreError::Triangulation`, `Nari2DCoreError::AlreadyExists` or
+    ///   `Nari2DCoreError::ThisIsABug` if the hull can't be inserted, see
+    ///   `EditMesh::retriangulate_constrained`.
+    pub fn recalculate_hull(&mut self, smoothness: usize) -> NCResult<()> {
         let hull = self.calculate_concave_hull(smoothness)?;
-        self.boarder_edges = hull;
-        Ok(())
+        let constraints = self
+            .edges
+            .values()
+            .filter(|edge| edge.locked)
+            .map(|edge| (edge.point0, edge.point1))
+            .chain(hull.points.into_iter().circular_tuple_windows())
+            .collect_vec();
+        let holes = self.holes.clone();
+        self.retriangulate_constrained(&constraints, &holes, true)
     }
 
     // http://paper.academicpub.org/Paper?id=15630
+    // points are inserted in order of distance from the seed triangle's circumcenter, so most of them
+    // land outside of the current hull. delaunay is kept by flipping after every insertion.
+    /// # Errors
+    ///
+    /// - `Nari2DCoreError::TooFewPoints` with fewer than 3 points.
+    /// - `Nari2DCoreError::AlreadyExists` if two points are on top of each other.
+    /// - `Nari2DCoreError::Triangulation` if every point is colinear, or a locked edge can't be
+    ///   kept.
+    /// - `Nari2DCoreError::ThisIsABug` if the triangulation falls apart on the way.
     pub fn retriangulate_mesh(&mut self) -> NCResult<()> {
-        const POINT_SHIFT_CONST: f32 = 0.000_001_f32;
+        let constraints = self
+            .edges
+            .values()
+            .filter(|edge| edge.locked)
+            .map(|edge| (edge.point0, edge.point1))
+            .collect_vec();
+        self.triangulate(&constraints)
+    }
 
-        if self.points.len() > 3 {
-            return Err(Nari2DCoreError::TooFewPoints(self.points.len() as u8));
+    fn triangulate(&mut self, constraints: &[(PointId, PointId)]) -> NCResult<()> {
+        if self.points.len() < 3 {
+            return Err(Nari2DCoreError::TooFewPoints(
+                u8::try_from(self.points.len()).unwrap_or(u8::MAX),
+            ));
         }
 
-        let mut rand = WyRand::new();
-        let mut shifted_amts = SecondaryMap::with_capacity(self.points.len());
-
-        self.points.iter_mut().for_each(|(id, pt)| {
-            let random_deviation = (rand.generate_range(-9..9) as f32) * POINT_SHIFT_CONST; // garuntee no points are colinear
-            pt.x += random_deviation;
-            shifted_amts.insert(id, random_deviation);
-        });
+        // check before we clear anything, so a failure leaves the mesh as is
+        if let Some((duplicate, _)) = self
+            .points
+            .values()
+            .sorted()
+            .tuple_windows()
+            .find(|(a, b)| a == b)
+        {
+            return Err(Nari2DCoreError::AlreadyExists(*duplicate));
+        }
 
-        let mut convex_hull = Vec::with_capacity(3);
+        let [seed_p0, seed_p1, seed_p2] = self.seed_triangle().ok_or_else(|| {
+            Nari2DCoreError::Triangulation("All points are colinear, no seed triangle".into())
+        })?;
+        let seed_center = {
+            let p0 = self.points.get(seed_p0);
+            let p1 = self.points.get(seed_p1);
+            let p2 = self.points.get(seed_p2);
+            match (p0, p1, p2) {
+                (Some(p0), Some(p1), Some(p2)) => Point2d::circumcenter(p0, p1, p2),
+                _ => {
+                    return Err(Nari2DCoreError::ThisIsABug(
+                        "Seed triangle points should exist!".into(),
+                    ))
+                }
+            }
+        };
 
-        // clear edges and relations
+        // clear edges and relations, a journal sees every triangle and then every edge go
+        if let Some(changes) = &mut self.changes {
+            changes.extend(self.triangles.iter().map(|(id, triangle)| {
+                Change::DisconnectTriangle {
+                    id,
+                    triangle: *triangle,
+                }
+            }));
+            changes.extend(self.edges.iter().map(|(id, edge)| Change::RemoveEdge {
+                id,
+                points: (edge.point0, edge.point1),
+                locked: edge.locked,
+            }));
+        }
         self.edges.clear();
         self.triangles.clear();
         self.point_edges.clear();
         self.boarder_edges.clear();
         self.triangle_edges.clear();
 
-        // seed triangle
-        let mut pts = self
+        self.connect_triangle([seed_p0, seed_p1, seed_p2].into())?;
+
+        let to_insert = self
             .points
             .iter()
-            .sorted()
-            .collect::<VecDeque<(PointId, &Point2d)>>();
-        let seed_tri_p0 = pts
-            .pop_front()
-            .ok_or(Nari2DCoreError::ThisIsABug("This should exist!".into()))?;
-        let seed_tri_p1 = pts
-            .pop_front()
-            .ok_or(Nari2DCoreError::ThisIsABug("This should exist!".into()))?;
-        let seed_tri_p2 = pts
-            .pop_front()
-            .ok_or(Nari2DCoreError::ThisIsABug("This should exist!".into()))?;
-        let seed_triangle = if Point2d::orientation(seed_tri_p0.1, seed_tri_p2.1, seed_tri_p1.1)
-            .is_counter_clock_wise()
-        {
-            Triangle {
-                point0: seed_tri_p0.0,
-                point1: seed_tri_p2.0,
-                point2: seed_tri_p1.0,
-            }
-        } else {
-            Triangle {
-                point0: seed_tri_p0.0,
-                point1: seed_tri_p1.0,
-                point2: seed_tri_p2.0,
-            }
-        };
-        let mut seed_triangle_p0p1 = Edge {
-            point0: Default::default(),
-            point1: Default::default(),
-            triangle0: None,
-            triangle1: None,
-        };
-    }
+            .filter(|(id, _)| *id != seed_p0 && *id != seed_p1 && *id != seed_p2)
+            .sorted_by(|(_, a), (_, b)| {
+                f32::total_cmp(&seed_center.distance2(a), &seed_center.distance2(b))
+            })
+            .map(|(id, _)| id)
+            .collect_vec();
 
-    // https://github.com/mourner/delaunator-rs/blob/master/src/lib.rs
-    pub fn closest(&self, point: &Point2d) -> Option<PointId> {
-        if self.points.len() == 0 {
-            return None;
+        // the mesh stays convex while it is built, so a walk that leaves it through the boarder
+        // means the point is outside. the walk starts next to the last point, which is close by
+        // as they are inserted in order of distance
+        let mut hint = None;
+        for point in to_insert {
+            let position = *self.points.get(point).ok_or_else(|| {
+                Nari2DCoreError::ThisIsABug("Inserted points should exist!".into())
+            })?;
+            let located = hint
+                .and_then(|start| self.walk(start, position))
+                .unwrap_or_else(|| self.locate_brute(position));
+            self.triangulate_point(point, located)?;
+            hint = self.any_triangle(point);
         }
-        let mut distance_min = f32::INFINITY;
-        let mut point_id = PointId::default();
 
-        for (id, pt) in self.points.iter() {
-            let dist = pt.distance2(point);
-            if dist > 0_f32 && dist < distance_min {
-                point_id = id;
-                distance_min = dist;
-            }
+        for (a, b) in constraints {
+            self.insert_constraint(*a, *b)?;
         }
+        self.carve();
 
-        if distance_min == f32::INFINITY {
-            None
-        } else {
-            Some(point_id)
+        Ok(())
+    }
+
+    // constraint edges are locked and kept by every later retriangulation. the region around each
+    // hole point is removed up to the nearest constraints, as is everything outside of them if
+    // `carve_exterior` is set.
+    /// # Errors
+    ///
+    /// - `Nari2DCoreError::DoesNotExist` if a constraint uses a point that is missing.
+    /// - `Nari2DCoreError::TooFewPoints`, `Nari2DCoreError::AlreadyExists` and
+    ///   `Nari2DCoreError::ThisIsABug` like `EditMesh::retriangulate_mesh`.
+    /// - `Nari2DCoreError::Triangulation` if every point is colinear, or a constraint leaves the
+    ///   mesh or crosses another one.
+    pub fn retriangulate_constrained(
+        &mut self,
+        constraints: &[(PointId, PointId)],
+        holes: &[Point2d],
+        carve_exterior: bool,
+    ) -> NCResult<()> {
+        for (a, b) in constraints {
+            for point in [a, b] {
+                if !self.points.contains_key(*point) {
+                    return Err(Nari2DCoreError::DoesNotExist(
+                        format!("Point {point}").into(),
+                    ));
+                }
+            }
         }
+
+        self.set_holes(holes.to_vec(), carve_exterior);
+        self.triangulate(constraints)
     }
 
-    // https://github.com/mourner/delaunator-rs/blob/master/src/lib.rs
-    pub fn closest_n(&self, point: &Point2d, n: u32) -> Option<Vec<PointId>> {
-        if self.points.len() == 0 {
-            return None;
+    // https://doi.org/10.1016/0045-7949(93)90239-A (Sloan)
+    // forces an edge between a and b by flipping away every edge crossing it, then locks it.
+    /// # Errors
+    ///
+    /// - `Nari2DCoreError::DoesNotExist` if either point is missing.
+    /// - `Nari2DCoreError::General` if `a` and `b` are the same point.
+    /// - `Nari2DCoreError::Triangulation` if the constraint leaves the mesh or crosses another one.
+    /// - `Nari2DCoreError::ThisIsABug` if flipping the crossing edges does not converge.
+    pub fn insert_constraint(&mut self, a: PointId, b: PointId) -> NCResult<()> {
+        let point_a = *self
+            .points
+            .get(a)
+            .ok_or_else(|| Nari2DCoreError::DoesNotExist(format!("Point {a}").into()))?;
+        let point_b = *self
+            .points
+            .get(b)
+            .ok_or_else(|| Nari2DCoreError::DoesNotExist(format!("Point {b}").into()))?;
+        if a == b {
+            return Err(Nari2DCoreError::General(
+                "A constraint needs two different points".into(),
+            ));
         }
-        let distanced = self
+
+        // a vertex sitting on the constraint splits it in two
+        let on_constraint = self
             .points
             .iter()
-            .sorted_by(|(_, a), (_, b)| {
-                let dist_a = point.distance2(a);
-                let dist_b = point.distance2(b);
-
-                f32::total_cmp(&dist_a, &dist_b)
+            .filter(|(id, point)| {
+                *id != a
+                    && *id != b
+                    && self
+                        .point_edges
+                        .get(*id)
+                        .is_some_and(|pe| !pe.edges.is_empty())
+                    && Point2d::orientation(&point_a, &point_b, point).is_colinear()
+                    && point.is_on_segment((&point_a, &point_b))
             })
-            .map(|(id, _)| id)
-            .take(n as usize)
-            .collect_vec();
+            .min_by(|(_, p), (_, q)| f32::total_cmp(&point_a.distance2(p), &point_a.distance2(q)))
+            .map(|(id, _)| id);
+        if let Some(between) = on_constraint {
+            self.insert_constraint(a, between)?;
+            return self.insert_constraint(between, b);
+        }
 
-        Some(distanced)
-    }
+        if let Some(edge) = self.edge_from_points(a, b).copied() {
+            return self.set_edge_locked(edge, true);
+        }
 
-    // https://github.com/mourner/delaunator-rs/blob/master/src/lib.rs
-    pub fn bbox_center(&self) -> Point2d {
-        let mut min_x = f32::INFINITY;
-        let mut min_y = f32::INFINITY;
-        let mut max_x = f32::NEG_INFINITY;
-        let mut max_y = f32::INFINITY;
+        let mut crossing = self
+            .edges
+            .iter()
+            .filter(|(_, edge)| self.crosses(edge, point_a, point_b))
+            .map(|(id, _)| id)
+            .collect::<VecDeque<EdgeId>>();
 
-        for point in self.points.values() {
-            min_x = min_x.min(point.x);
-            min_y = min_y.min(point.y);
-            max_x = max_x.min(point.x);
-            max_y = max_y.min(point.y);
+        if crossing.is_empty() {
+            return Err(Nari2DCoreError::Triangulation(
+                format!("Constraint {point_a} - {point_b} leaves the mesh").into(),
+            ));
         }
-
+        if crossing
+            .iter()
+            .any(|edge| self.edges.get(*edge).is_some_and(|e| e.locked))
+        {
+            return Err(Nari2DCoreError::Triangulation(
+                format!("Constraint {point_a} - {point_b} crosses another constraint").into(),
+            ));
+        }
+
+        let mut attempts_left = crossing.len().pow(2) * 4 + 16;
+        let mut flipped = vec![];
+        while let Some(edge) = crossing.pop_front() {
+            if attempts_left == 0 {
+                return Err(Nari2DCoreError::ThisIsABug(
+                    "Constraint insertion did not converge".into(),
+                ));
+            }
+            attempts_left -= 1;
+
+            match self.flip(edge) {
+                Some(new_edge) => {
+                    let crosses = self
+                        .edges
+                        .get(new_edge)
+                        .is_some_and(|e| self.crosses(e, point_a, point_b));
+                    if crosses {
+                        crossing.push_back(new_edge);
+                    } else {
+                        flipped.push(new_edge);
+                    }
+                }
+                None => crossing.push_back(edge),
+            }
+        }
+
+        let constraint = *self.edge_from_points(a, b).ok_or_else(|| {
+            Nari2DCoreError::ThisIsABug("Constraint edge missing after flipping".into())
+        })?;
+        self.set_edge_locked(constraint, true)?;
+
+        let flipped = flipped
+            .into_iter()
+            .filter_map(|edge| self.edges.get(edge).map(|e| (e.point0, e.point1)))
+            .collect_vec();
+        self.legalize(flipped);
+        Ok(())
+    }
+
+    // the edge must have two triangles forming a convex quad. returns the new edge.
+    /// # Errors
+    ///
+    /// - `Nari2DCoreError::DoesNotExist` if the edge is missing.
+    /// - `Nari2DCoreError::Locked` if the edge is locked.
+    /// - `Nari2DCoreError::NonManifoldStructure` if the edge is on the boarder.
+    /// - `Nari2DCoreError::InvertsTriangle` if its quad is not convex.
+    pub fn flip_edge(&mut self, edge: EdgeId) -> NCResult<EdgeId> {
+        let real_edge = self
+            .edges
+            .get(edge)
+            .ok_or_else(|| Nari2DCoreError::DoesNotExist(format!("Edge {edge}").into()))?;
+        if real_edge.locked {
+            return Err(Nari2DCoreError::Locked(format!("Edge {edge}").into()));
+        }
+
+        let (_, _, left, right) = self
+            .edge_quad(edge)
+            .ok_or(Nari2DCoreError::NonManifoldStructure)?;
+        if self.edge_from_points(left, right).is_some() {
+            return Err(Nari2DCoreError::NonManifoldStructure);
+        }
+
+        self.flip(edge).ok_or(Nari2DCoreError::InvertsTriangle)
+    }
+
+    // inserts the midpoint of the edge, splitting the triangles on either side in two.
+    /// # Errors
+    ///
+    /// The mesh is left as it was.
+    ///
+    /// - `Nari2DCoreError::DoesNotExist` if the edge is missing.
+    /// - `Nari2DCoreError::NonManifoldStructure` if one of its points is missing or a triangle
+    ///   next to it is broken.
+    pub fn split_edge(&mut self, edge: EdgeId) -> NCResult<PointId> {
+        let real_edge = *self
+            .edges
+            .get(edge)
+            .ok_or_else(|| Nari2DCoreError::DoesNotExist(format!("Edge {edge}").into()))?;
+        let (Some(a), Some(b)) = (
+            self.points.get(real_edge.point0),
+            self.points.get(real_edge.point1),
+        ) else {
+            return Err(Nari2DCoreError::NonManifoldStructure);
+        };
+
+        let midpoint = (*a + *b) / 2_f32;
+        self.or_revert(|mesh| {
+            let id = mesh.insert_interpolated(midpoint, Located::OnEdge(edge));
+            mesh.split_edge_at(edge, id)?;
+            Ok(id)
+        })
+    }
+
+    // merges both points of the edge into one, removing the edge's triangles. a point on the
+    // boarder or a locked edge keeps its position, otherwise the merged point is the midpoint.
+    // returns the point that was kept.
+    /// # Errors
+    ///
+    /// - `Nari2DCoreError::DoesNotExist` if the edge is missing.
+    /// - `Nari2DCoreError::NonManifoldStructure` if collapsing would pinch two boundaries together.
+    /// - `Nari2DCoreError::InvertsTriangle` if it would fold a triangle over.
+    pub fn collapse_edge(&mut self, edge: EdgeId) -> NCResult<PointId> {
+        let real_edge = *self
+            .edges
+            .get(edge)
+            .ok_or_else(|| Nari2DCoreError::DoesNotExist(format!("Edge {edge}").into()))?;
+        let (a, b) = (real_edge.point0, real_edge.point1);
+        let (Some(point_a), Some(point_b)) = (self.points.get(a), self.points.get(b)) else {
+            return Err(Nari2DCoreError::NonManifoldStructure);
+        };
+
+        let (pinned_a, pinned_b) = (self.is_pinned(a), self.is_pinned(b));
+        // two boundaries would get pinched together
+        if pinned_a && pinned_b && !(real_edge.locked || real_edge.is_boarder()) {
+            return Err(Nari2DCoreError::NonManifoldStructure);
+        }
+
+        let (keep, remove, position) = match (pinned_a, pinned_b) {
+            (true, false) => (a, b, *point_a),
+            (false, true) => (b, a, *point_b),
+            _ => (a, b, (*point_a + *point_b) / 2_f32),
+        };
+
+        self.collapse_edge_to(edge, keep, remove, position)
+    }
+
+    fn collapse_edge_to(
+        &mut self,
+        edge: EdgeId,
+        keep: PointId,
+        remove: PointId,
+        position: Point2d,
+    ) -> NCResult<PointId> {
+        let real_edge = *self
+            .edges
+            .get(edge)
+            .ok_or_else(|| Nari2DCoreError::DoesNotExist(format!("Edge {edge}").into()))?;
+        if !(real_edge.contains_point(keep) && real_edge.contains_point(remove)) || keep == remove {
+            return Err(Nari2DCoreError::General(
+                "Collapsed points must be the edge's points".into(),
+            ));
+        }
+
+        let uv = self.interpolate_uv(position, Located::OnEdge(edge));
+
+        // link condition: the only shared neighbours may be the tips of the edge's triangles
+        let opposite = [real_edge.triangle0, real_edge.triangle1]
+            .into_iter()
+            .flatten()
+            .filter_map(|triangle| self.triangles.get(triangle))
+            .filter_map(|triangle| {
+                triangle
+                    .points()
+                    .into_iter()
+                    .find(|pt| *pt != keep && *pt != remove)
+            })
+            .collect_vec();
+        let keep_neighbours = self.neighbours(keep);
+        if self
+            .neighbours(remove)
+            .iter()
+            .filter(|pt| keep_neighbours.contains(pt))
+            .any(|pt| !opposite.contains(pt))
+        {
+            return Err(Nari2DCoreError::NonManifoldStructure);
+        }
+
+        let around_keep = self.triangles_around(keep);
+        let around_remove = self.triangles_around(remove);
+        let mut replaced = Vec::with_capacity(around_remove.len());
+
+        // nothing left around the kept point may fold over
+        for triangle in around_keep.iter().chain(&around_remove) {
+            let Some(real_triangle) = self.triangles.get(*triangle) else {
+                continue;
+            };
+            if real_triangle.contains(keep) && real_triangle.contains(remove) {
+                continue;
+            }
+            let moved =
+                Triangle::from(
+                    real_triangle
+                        .points()
+                        .map(|pt| if pt == remove { keep } else { pt }),
+                );
+            let positions = moved
+                .points()
+                .map(|pt| {
+                    if pt == keep {
+                        Some(position)
+                    } else {
+                        self.points.get(pt).copied()
+                    }
+                })
+                .into_iter()
+                .collect::<Option<Vec<Point2d>>>()
+                .ok_or(Nari2DCoreError::NonManifoldStructure)?;
+            if !Point2d::orientation(&positions[0], &positions[1], &positions[2])
+                .is_counter_clock_wise()
+            {
+                return Err(Nari2DCoreError::InvertsTriangle);
+            }
+            if real_triangle.contains(remove) {
+                replaced.push((*triangle, moved));
+            }
+        }
+
+        let locked = self
+            .point_edges
+            .get(remove)
+            .map(|pe| pe.edges.to_vec())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|e| self.edges.get(e))
+            .filter(|e| e.locked)
+            .filter_map(|e| e.other_point(remove))
+            .filter(|pt| *pt != keep)
+            .collect_vec();
+
+        let mut old_edges = vec![];
+        for triangle in &around_remove {
+            if let Some((_, edges)) = self.disconnect_triangle(*triangle) {
+                old_edges.extend(edges.edges);
+            }
+        }
+        for (_, moved) in replaced {
+            self.connect_triangle(moved)?;
+        }
+        for other in locked {
+            if let Some(edge) = self.edge_from_points(keep, other).copied() {
+                self.set_edge_locked(edge, true)?;
+            }
+        }
+        self.prune_edges(old_edges);
+
+        self.forget_point(remove);
+        self.point_edges.remove(remove);
+        self.move_point(keep, position);
+        if uv.is_some() {
+            self.put_uv(keep, uv);
+        }
+        Ok(keep)
+    }
+
+    // collapses the shortest edges until there are at most `target_points` points left or the
+    // next collapse would move a point further than `max_error`. boarder and locked edges are
+    // never collapsed, so the outline and constraints are kept as they are.
+    pub fn decimate(&mut self, options: DecimateOptions) -> Decimation {
+        let mut decimation = Decimation::default();
+
+        while self.points.len() > options.target_points {
+            let candidates = self
+                .edges
+                .iter()
+                .filter(|(_, edge)| !(edge.locked || edge.is_boarder()))
+                .filter_map(|(id, edge)| Some((id, self.collapse_cost(edge)?)))
+                .sorted_by(|(_, a), (_, b)| f32::total_cmp(a, b))
+                .collect_vec();
+            // costs around a collapse are stale until the next pass
+            let mut touched = HashSet::new();
+            let mut progressed = false;
+
+            for (edge, cost) in candidates {
+                if self.points.len() <= options.target_points
+                    || options.max_error.is_some_and(|max| cost > max)
+                {
+                    break;
+                }
+                let real_edge = *some_or_continue!(self.edges.get(edge));
+                if touched.contains(&real_edge.point0) || touched.contains(&real_edge.point1) {
+                    continue;
+                }
+                let Ok(keep) = self.collapse_edge(edge) else {
+                    continue;
+                };
+                let remove = if keep == real_edge.point0 {
+                    real_edge.point1
+                } else {
+                    real_edge.point0
+                };
+
+                decimation.remap.insert(remove, keep);
+                decimation.error = decimation.error.max(cost);
+                progressed = true;
+                touched.insert(keep);
+                touched.extend(self.neighbours(keep));
+
+                let star = self
+                    .triangles_around(keep)
+                    .into_iter()
+                    .filter_map(|triangle| self.triangles.get(triangle))
+                    .flat_map(|triangle| {
+                        let [p0, p1, p2] = triangle.points();
+                        [(p0, p1), (p1, p2), (p2, p0)]
+                    })
+                    .collect_vec();
+                self.legalize(star);
+            }
+
+            if !progressed {
+                break;
+            }
+        }
+
+        // points that were merged into a point that was merged later on
+        let removed = decimation.remap.keys().collect_vec();
+        for point in removed {
+            let mut to = decimation.resolve(point);
+            while let Some(next) = decimation.remap.get(to) {
+                to = *next;
+            }
+            decimation.remap.insert(point, to);
+        }
+        decimation
+    }
+
+    // how far collapsing the edge moves its points, None if it can't be collapsed
+    fn collapse_cost(&self, edge: &Edge) -> Option<f32> {
+        let length = self
+            .points
+            .get(edge.point0)?
+            .distance(self.points.get(edge.point1)?);
+        match (self.is_pinned(edge.point0), self.is_pinned(edge.point1)) {
+            (true, true) => None,
+            (true, false) | (false, true) => Some(length),
+            (false, false) => Some(length / 2_f32),
+        }
+    }
+
+    // true for points on the boarder or on a locked edge
+    fn is_pinned(&self, point: PointId) -> bool {
+        self.point_edges.get(point).is_some_and(|pe| {
+            pe.edges
+                .iter()
+                .filter_map(|e| self.edges.get(*e))
+                .any(|e| e.locked || e.is_boarder())
+        })
+    }
+
+    fn neighbours(&self, point: PointId) -> Vec<PointId> {
+        self.point_edges
+            .get(point)
+            .map(|pe| {
+                pe.edges
+                    .iter()
+                    .filter_map(|e| self.edges.get(*e))
+                    .filter_map(|e| e.other_point(point))
+                    .collect_vec()
+            })
+            .unwrap_or_default()
+    }
+
+    fn triangles_around(&self, point: PointId) -> Vec<TriangleId> {
+        self.point_edges
+            .get(point)
+            .map(|pe| {
+                pe.edges
+                    .iter()
+                    .filter_map(|e| self.edges.get(*e))
+                    .flat_map(|e| [e.triangle0, e.triangle1])
+                    .flatten()
+                    .unique()
+                    .collect_vec()
+            })
+            .unwrap_or_default()
+    }
+
+    /// # Errors
+    ///
+    /// `Nari2DCoreError::DoesNotExist` if the edge is missing.
+    pub fn set_edge_locked(&mut self, edge: EdgeId, locked: bool) -> NCResult<()> {
+        let was = std::mem::replace(
+            &mut self
+                .edges
+                .get_mut(edge)
+                .ok_or_else(|| Nari2DCoreError::DoesNotExist(format!("Edge {edge}").into()))?
+                .locked,
+            locked,
+        );
+        if was != locked {
+            self.log(Change::LockEdge { id: edge, was });
+        }
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn holes(&self) -> &[Point2d] {
+        &self.holes
+    }
+
+    fn set_holes(&mut self, holes: Vec<Point2d>, carve_exterior: bool) {
+        let holes = std::mem::replace(&mut self.holes, holes);
+        let carve_exterior = std::mem::replace(&mut self.carve_exterior, carve_exterior);
+        self.log(Change::SetHoles {
+            holes,
+            carve_exterior,
+        });
+    }
+
+    // walks the boarder edges into closed loops. where loops touch at a point, which way they
+    // continue is arbitrary
+    #[must_use]
+    pub fn boarder_loops(&self) -> Vec<BoarderLoop> {
+        let mut outgoing: HashMap<PointId, Vec<(PointId, EdgeId)>> = HashMap::new();
+        for edge in &self.boarder_edges {
+            let (from, to) = some_or_continue!(self.boarder_edge_winding(*edge));
+            outgoing.entry(from).or_default().push((to, *edge));
+        }
+
+        let mut loops = vec![];
+        for start in self
+            .boarder_edges
+            .iter()
+            .filter_map(|edge| self.boarder_edge_winding(*edge))
+            .map(|(from, _)| from)
+        {
+            let mut points = vec![];
+            let mut edges = vec![];
+            let mut current = start;
+            while let Some((to, edge)) = outgoing.get_mut(&current).and_then(Vec::pop) {
+                points.push(current);
+                edges.push(edge);
+                current = to;
+            }
+            if points.is_empty() {
+                continue;
+            }
+
+            let signed_area = points
+                .iter()
+                .filter_map(|point| self.points.get(*point))
+                .collect_vec()
+                .into_iter()
+                .circular_tuple_windows()
+                .map(|(a, b)| a.x * b.y - b.x * a.y)
+                .sum::<f32>()
+                / 2_f32;
+            let kind = if signed_area > 0_f32 {
+                LoopKind::Outer
+            } else {
+                LoopKind::Hole
+            };
+            loops.push(BoarderLoop {
+                points,
+                edges,
+                kind,
+                signed_area,
+            });
+        }
+        loops
+    }
+
+    // groups the triangles into sets that are connected through their points. points that are
+    // not part of any triangle are left out.
+    #[must_use]
+    pub fn connected_components(&self) -> Vec<Vec<TriangleId>> {
+        let mut component_of: SecondaryMap<TriangleId, usize> = SecondaryMap::new();
+        let mut components = vec![];
+
+        for start in self.triangles.keys() {
+            if component_of.contains_key(start) {
+                continue;
+            }
+            let component = components.len();
+            let mut triangles = vec![];
+            let mut stack = vec![start];
+            component_of.insert(start, component);
+
+            while let Some(triangle) = stack.pop() {
+                triangles.push(triangle);
+                let real_triangle = some_or_continue!(self.triangles.get(triangle));
+                for point in real_triangle.points() {
+                    for around in self.triangles_around(point) {
+                        if !component_of.contains_key(around) {
+                            component_of.insert(around, component);
+                            stack.push(around);
+                        }
+                    }
+                }
+            }
+            components.push(triangles);
+        }
+        components
+    }
+
+    // copies every connected component into its own mesh, keeping uvs, locked edges and holes
+    /// # Errors
+    ///
+    /// `Nari2DCoreError::DoesNotExist` or `Nari2DCoreError::NonManifoldStructure` if the mesh is
+    /// broken, see `EditMesh::validate`.
+    pub fn split_components(&self) -> NCResult<Vec<SubMesh>> {
+        let mut sub_meshes = vec![];
+
+        for component in self.connected_components() {
+            let mut mesh = EditMesh {
+                holes: self.holes.clone(),
+                carve_exterior: self.carve_exterior,
+                ..EditMesh::default()
+            };
+            let mut points = SecondaryMap::new();
+
+            for triangle in component {
+                let real_triangle = some_or_continue!(self.triangles.get(triangle));
+                let mut new_points = [PointId::default(); 3];
+                for (new_point, point) in new_points.iter_mut().zip(real_triangle.points()) {
+                    if let Some(existing) = points.get(point) {
+                        *new_point = *existing;
+                        continue;
+                    }
+                    let position = *self.points.get(point).ok_or_else(|| {
+                        Nari2DCoreError::DoesNotExist(format!("Point {point}").into())
+                    })?;
+                    *new_point = mesh.insert_point(position);
+                    if let Some(uv) = self.uvs.get(point) {
+                        mesh.uvs.insert(*new_point, *uv);
+                    }
+                    points.insert(point, *new_point);
+                }
+                mesh.connect_triangle(new_points.into())?;
+            }
+
+            for edge in self.edges.values().filter(|edge| edge.locked) {
+                let (Some(a), Some(b)) = (points.get(edge.point0), points.get(edge.point1)) else {
+                    continue;
+                };
+                if let Some(new_edge) = mesh.edge_from_points(*a, *b).copied() {
+                    mesh.set_edge_locked(new_edge, true)?;
+                }
+            }
+
+            sub_meshes.push(SubMesh { mesh, points });
+        }
+
+        Ok(sub_meshes)
+    }
+
+    /// # Errors
+    ///
+    /// - `Nari2DCoreError::General` with more points than fit in a u32.
+    /// - `Nari2DCoreError::DoesNotExist` if a triangle or locked edge uses a point that is missing.
+    pub fn to_compact(&self) -> NCResult<CompactMesh> {
+        let mut indices = SecondaryMap::new();
+        for (index, point) in self.points.keys().enumerate() {
+            let index = u32::try_from(index)
+                .map_err(|_| Nari2DCoreError::General("Too many points to store".into()))?;
+            indices.insert(point, index);
+        }
+        let index_of = |point: PointId| {
+            indices
+                .get(point)
+                .copied()
+                .ok_or_else(|| Nari2DCoreError::DoesNotExist(format!("Point {point}").into()))
+        };
+
+        Ok(CompactMesh {
+            points: self.points.values().copied().collect(),
+            uvs: self
+                .points
+                .keys()
+                .map(|point| self.uvs.get(point).copied())
+                .collect(),
+            triangles: self
+                .triangles
+                .values()
+                .map(|triangle| {
+                    let [p0, p1, p2] = triangle.points();
+                    Ok([index_of(p0)?, index_of(p1)?, index_of(p2)?])
+                })
+                .collect::<NCResult<_>>()?,
+            locked_edges: self
+                .edges
+                .values()
+                .filter(|edge| edge.locked)
+                .map(|edge| Ok([index_of(edge.point0)?, index_of(edge.point1)?]))
+                .collect::<NCResult<_>>()?,
+            holes: self.holes.clone(),
+            carve_exterior: self.carve_exterior,
+        })
+    }
+
+    // rebuilds the connectivity of a compact mesh, failing if the triangles don't form a valid
+    // mesh
+    /// # Errors
+    ///
+    /// - `Nari2DCoreError::DoesNotExist` if a triangle or locked edge uses a point that is not
+    ///   there, or a locked edge is not the side of a triangle.
+    /// - `Nari2DCoreError::NonManifoldStructure` if more than two triangles share a side.
+    /// - `Nari2DCoreError::InvalidMesh` if the triangles connect but don't pass
+    ///   `EditMesh::validate`.
+    pub fn from_compact(compact: &CompactMesh) -> NCResult<EditMesh> {
+        let mut mesh = EditMesh {
+            holes: compact.holes.clone(),
+            carve_exterior: compact.carve_exterior,
+            ..EditMesh::default()
+        };
+        let ids = compact
+            .points
+            .iter()
+            .map(|point| mesh.insert_point(*point))
+            .collect_vec();
+        for (id, uv) in ids.iter().zip(&compact.uvs) {
+            if let Some(uv) = uv {
+                mesh.uvs.insert(*id, *uv);
+            }
+        }
+
+        let id_of = |index: u32| {
+            usize::try_from(index)
+                .ok()
+                .and_then(|index| ids.get(index))
+                .copied()
+                .ok_or_else(|| Nari2DCoreError::DoesNotExist(format!("Point {index}").into()))
+        };
+        for [p0, p1, p2] in &compact.triangles {
+            mesh.connect_triangle([id_of(*p0)?, id_of(*p1)?, id_of(*p2)?].into())?;
+        }
+        for [a, b] in &compact.locked_edges {
+            let edge = mesh
+                .edge_from_points(id_of(*a)?, id_of(*b)?)
+                .copied()
+                .ok_or_else(|| Nari2DCoreError::DoesNotExist(format!("Edge {a} {b}").into()))?;
+            mesh.set_edge_locked(edge, true)?;
+        }
+
+        mesh.validate()?;
+        Ok(mesh)
+    }
+
+    // checks every connectivity invariant, returning all of the broken ones as
+    // Nari2DCoreError::InvalidMesh
+    /// # Errors
+    ///
+    /// `Nari2DCoreError::InvalidMesh` with every broken invariant.
+    pub fn validate(&self) -> NCResult<()> {
+        let mut violations = vec![];
+        let uses = self.validate_triangles(&mut violations);
+        self.validate_edges(&uses, &mut violations);
+        self.validate_boarder(&mut violations);
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(Nari2DCoreError::InvalidMesh(violations))
+        }
+    }
+
+    // returns how many triangles use each edge
+    fn validate_triangles(
+        &self,
+        violations: &mut Vec<MeshViolation>,
+    ) -> SecondaryMap<EdgeId, usize> {
+        let mut uses: SecondaryMap<EdgeId, usize> = SecondaryMap::new();
+
+        for (id, triangle) in &self.triangles {
+            for point in triangle.points() {
+                if !self.points.contains_key(point) {
+                    violations.push(MeshViolation::MissingPoint {
+                        triangle: id,
+                        point,
+                    });
+                }
+            }
+            if let Some([p0, p1, p2]) = self.triangle_positions(triangle) {
+                if !Point2d::orientation(&p0, &p1, &p2).is_counter_clock_wise() {
+                    violations.push(MeshViolation::WrongWinding(id));
+                }
+            }
+
+            let Some(triangle_edge) = self.triangle_edges.get(id) else {
+                violations.push(MeshViolation::MissingTriangleEdge(id));
+                continue;
+            };
+            let points = triangle.points();
+            for (side, edge_id) in (0_u8..).zip(triangle_edge.edges) {
+                let a = points[usize::from(side)];
+                let b = points[(usize::from(side) + 1) % 3];
+                match self.edges.get(edge_id) {
+                    Some(edge) if a != b && edge.contains_point(a) && edge.contains_point(b) => {
+                        if let Some(count) = uses.entry(edge_id) {
+                            *count.or_insert(0) += 1;
+                        }
+                        if edge.triangle0 != Some(id) && edge.triangle1 != Some(id) {
+                            violations.push(MeshViolation::MissingBackReference {
+                                edge: edge_id,
+                                triangle: id,
+                            });
+                        }
+                    }
+                    _ => violations.push(MeshViolation::WrongTriangleEdge {
+                        triangle: id,
+                        edge: edge_id,
+                        side,
+                    }),
+                }
+            }
+        }
+
+        uses
+    }
+
+    fn validate_edges(
+        &self,
+        uses: &SecondaryMap<EdgeId, usize>,
+        violations: &mut Vec<MeshViolation>,
+    ) {
+        let listed_boarder = self.boarder_edges.iter().copied().collect::<HashSet<_>>();
+        for (id, edge) in &self.edges {
+            for point in [edge.point0, edge.point1] {
+                if !self.points.contains_key(point) {
+                    violations.push(MeshViolation::DanglingEdge { edge: id, point });
+                } else if !self
+                    .point_edges
+                    .get(point)
+                    .is_some_and(|point_edge| point_edge.edges.contains(&id))
+                {
+                    violations.push(MeshViolation::IncompletePointEdge { point, edge: id });
+                }
+            }
+            for triangle in [edge.triangle0, edge.triangle1].into_iter().flatten() {
+                if !self
+                    .triangle_edges
+                    .get(triangle)
+                    .is_some_and(|triangle_edge| triangle_edge.edges.contains(&id))
+                {
+                    violations.push(MeshViolation::StaleBackReference { edge: id, triangle });
+                }
+            }
+
+            let count = uses.get(id).copied().unwrap_or_default();
+            if count > 2 {
+                violations.push(MeshViolation::NonManifoldEdge { edge: id, count });
+            }
+
+            match (edge.is_boarder(), listed_boarder.contains(&id)) {
+                (true, false) => violations.push(MeshViolation::MissingBoarderEdge(id)),
+                (false, true) => violations.push(MeshViolation::StaleBoarderEdge(id)),
+                _ => {}
+            }
+        }
+
+        for (point, point_edge) in &self.point_edges {
+            for edge in point_edge.edges() {
+                if !self
+                    .edges
+                    .get(*edge)
+                    .is_some_and(|real_edge| real_edge.contains_point(point))
+                {
+                    violations.push(MeshViolation::StalePointEdge { point, edge: *edge });
+                }
+            }
+        }
+    }
+
+    fn validate_boarder(&self, violations: &mut Vec<MeshViolation>) {
+        // walking the boarder with the mesh on the left, every point has to be left as often
+        // as it is entered for the boarder to be closed loops
+        let mut degree: SecondaryMap<PointId, isize> = SecondaryMap::new();
+        for edge in &self.boarder_edges {
+            let (from, to) = some_or_continue!(self.boarder_edge_winding(*edge));
+            if let Some(out) = degree.entry(from) {
+                *out.or_insert(0) += 1;
+            }
+            if let Some(into) = degree.entry(to) {
+                *into.or_insert(0) -= 1;
+            }
+        }
+        violations.extend(
+            degree
+                .iter()
+                .filter(|(_, balance)| **balance != 0)
+                .map(|(point, _)| MeshViolation::OpenBoarder(point)),
+        );
+    }
+
+    // true if the edge crosses the open segment a - b, touching at the ends does not count
+    fn crosses(&self, edge: &Edge, a: Point2d, b: Point2d) -> bool {
+        let (Some(p0), Some(p1)) = (self.points.get(edge.point0), self.points.get(edge.point1))
+        else {
+            return false;
+        };
+
+        let sides = [
+            Point2d::orientation(&a, &b, p0),
+            Point2d::orientation(&a, &b, p1),
+            Point2d::orientation(p0, p1, &a),
+            Point2d::orientation(p0, p1, &b),
+        ];
+
+        !sides.iter().any(Orientation::is_colinear) && sides[0] != sides[1] && sides[2] != sides[3]
+    }
+
+    // https://www.cs.cmu.edu/~quake/tripaper/triangle3.html
+    // Ruppert's refinement: split encroached segments (boarder and locked edges) at their midpoint,
+    // otherwise insert the circumcenter of the worst triangle, unless that would encroach a segment.
+    /// # Errors
+    ///
+    /// `Nari2DCoreError::DoesNotExist` or `Nari2DCoreError::NonManifoldStructure` if the mesh is
+    /// broken, see `EditMesh::validate`.
+    pub fn refine(&mut self, options: RefineOptions) -> NCResult<RefineReport> {
+        // segments shorter than this are never split, so small input angles can't loop forever
+        let min_segment = {
+            let center = self.bbox_center();
+            let radius = self
+                .points
+                .values()
+                .map(|pt| pt.distance(&center))
+                .fold(0_f32, f32::max);
+            radius * 1e-4
+        };
+
+        let mut report = RefineReport::default();
+        let mut given_up = Vec::new();
+
+        'refine: loop {
+            let mut progressed = false;
+
+            while let Some(segment) = self.encroached_segment(min_segment) {
+                if report.points_added >= options.max_steiner_points {
+                    break 'refine;
+                }
+                self.split_segment(segment)?;
+                report.points_added += 1;
+                progressed = true;
+            }
+
+            let bad = self
+                .triangles
+                .iter()
+                .filter(|(id, _)| !given_up.contains(id))
+                .filter_map(|(id, triangle)| {
+                    let (min_angle, area) = self.triangle_quality(triangle)?;
+                    let badness = options.badness(min_angle, area)?;
+                    Some((id, badness))
+                })
+                .sorted_by(|(_, a), (_, b)| f32::total_cmp(b, a))
+                .map(|(id, _)| id)
+                .collect_vec();
+
+            for triangle in bad {
+                if report.points_added >= options.max_steiner_points {
+                    break 'refine;
+                }
+                // earlier insertions in this pass may have already replaced it
+                let Some(real_triangle) = self.triangles.get(triangle).copied() else {
+                    continue;
+                };
+                let Some([p0, p1, p2]) = self.triangle_positions(&real_triangle) else {
+                    continue;
+                };
+                let center = Point2d::circumcenter(&p0, &p1, &p2);
+
+                let encroached = self
+                    .segments()
+                    .filter(|segment| self.encroaches(*segment, center, min_segment))
+                    .collect_vec();
+                if !encroached.is_empty() {
+                    for segment in encroached {
+                        if report.points_added >= options.max_steiner_points {
+                            break 'refine;
+                        }
+                        if self.edges.contains_key(segment) {
+                            self.split_segment(segment)?;
+                            report.points_added += 1;
+                        }
+                    }
+                    // the segments changed, go back to checking them first
+                    continue 'refine;
+                }
+
+                match self.locate(center) {
+                    located @ (Located::InTriangle(_) | Located::OnEdge(_)) => {
+                        let id = self.insert_interpolated(center, located);
+                        self.triangulate_point(id, located)?;
+                        report.points_added += 1;
+                        progressed = true;
+                    }
+                    Located::OnVertex(_) | Located::Outside => given_up.push(triangle),
+                }
+            }
+
+            if !progressed {
+                break;
+            }
+        }
+
+        report.unfixed = self
+            .triangles
+            .iter()
+            .filter(|(_, triangle)| {
+                self.triangle_quality(triangle)
+                    .and_then(|(min_angle, area)| options.badness(min_angle, area))
+                    .is_some()
+            })
+            .map(|(id, _)| id)
+            .collect();
+        Ok(report)
+    }
+
+    // (smallest angle, area)
+    fn triangle_quality(&self, triangle: &Triangle) -> Option<(Angle, f32)> {
+        let points = self.triangle_positions(triangle)?;
+        let mut min_angle = f32::INFINITY;
+
+        for corner in 0..3 {
+            let at = points[corner];
+            let next = points[(corner + 1) % 3] - at;
+            let prev = points[(corner + 2) % 3] - at;
+            let cross = next.x * prev.y - next.y * prev.x;
+            let dot = next.x * prev.x + next.y * prev.y;
+            min_angle = min_angle.min(cross.abs().atan2(dot));
+        }
+
+        let [p0, p1, p2] = points;
+        let area = ((p1.x - p0.x) * (p2.y - p0.y) - (p1.y - p0.y) * (p2.x - p0.x)) / 2_f32;
+        Some((Angle::new(min_angle), area))
+    }
+
+    fn segments(&self) -> impl Iterator<Item = EdgeId> + '_ {
+        self.edges
+            .iter()
+            .filter(|(_, edge)| edge.locked || edge.is_boarder())
+            .map(|(id, _)| id)
+    }
+
+    fn is_splittable(&self, segment: EdgeId, min_segment: f32) -> Option<(Point2d, Point2d)> {
+        let edge = self.edges.get(segment)?;
+        let a = *self.points.get(edge.point0)?;
+        let b = *self.points.get(edge.point1)?;
+        (a.distance(&b) > min_segment).then_some((a, b))
+    }
+
+    // a point encroaches a segment if it lies strictly inside of its diametral circle
+    fn encroaches(&self, segment: EdgeId, point: Point2d, min_segment: f32) -> bool {
+        let Some((a, b)) = self.is_splittable(segment, min_segment) else {
+            return false;
+        };
+        let to_a = a - point;
+        let to_b = b - point;
+        to_a.x * to_b.x + to_a.y * to_b.y < 0_f32
+    }
+
+    fn encroached_segment(&self, min_segment: f32) -> Option<EdgeId> {
+        self.segments().find(|segment| {
+            let Some(edge) = self.edges.get(*segment) else {
+                return false;
+            };
+            [edge.triangle0, edge.triangle1]
+                .into_iter()
+                .flatten()
+                .filter_map(|triangle| self.triangles.get(triangle))
+                .filter_map(|triangle| {
+                    triangle
+                        .points()
+                        .into_iter()
+                        .find(|pt| !edge.contains_point(*pt))
+                })
+                .filter_map(|opposite| self.points.get(opposite))
+                .any(|opposite| self.encroaches(*segment, *opposite, min_segment))
+        })
+    }
+
+    fn split_segment(&mut self, segment: EdgeId) -> NCResult<PointId> {
+        let edge = *self
+            .edges
+            .get(segment)
+            .ok_or_else(|| Nari2DCoreError::DoesNotExist(format!("Edge {segment}").into()))?;
+        let (Some(a), Some(b)) = (self.points.get(edge.point0), self.points.get(edge.point1))
+        else {
+            return Err(Nari2DCoreError::NonManifoldStructure);
+        };
+
+        let midpoint = self.insert_interpolated((*a + *b) / 2_f32, Located::OnEdge(segment));
+        let to_legalize = self.split_edge_at(segment, midpoint)?;
+        self.legalize(to_legalize);
+        Ok(midpoint)
+    }
+
+    // relaxes every point that is not on the boarder, on a locked edge or in `pinned`, flipping
+    // edges after every iteration so the mesh stays delaunay. a point only moves as far as it can
+    // without folding over one of its triangles, and its uv follows the texture under it.
+    pub fn smooth(&mut self, options: SmoothOptions, pinned: &[PointId]) {
+        let pinned: HashSet<PointId> = pinned.iter().copied().collect();
+        let free = self
+            .points
+            .keys()
+            .filter(|point| !pinned.contains(point) && !self.is_pinned(*point))
+            .collect_vec();
+        let strength = options.strength.clamp(0_f32, 1_f32);
+
+        for _ in 0..options.iterations {
+            for point in &free {
+                let target = some_or_continue!(self.smoothing_target(*point, options.method));
+                self.relax_point(*point, target, strength);
+            }
+
+            let edges = self
+                .edges
+                .values()
+                .map(|edge| (edge.point0, edge.point1))
+                .collect_vec();
+            self.legalize(edges);
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn smoothing_target(&self, point: PointId, method: SmoothingMethod) -> Option<Point2d> {
+        let position = *self.points.get(point)?;
+        let neighbours = self
+            .neighbours(point)
+            .into_iter()
+            .filter_map(|neighbour| self.points.get(neighbour).map(|pos| (neighbour, *pos)))
+            .collect_vec();
+        if neighbours.is_empty() {
+            return None;
+        }
+        let uniform = neighbours
+            .iter()
+            .fold(Point2d::default(), |sum, (_, neighbour)| sum + *neighbour)
+            / neighbours.len() as f32;
+
+        let target = match method {
+            SmoothingMethod::Laplacian => Some(uniform),
+            SmoothingMethod::AreaWeighted => self.area_weighted_target(point),
+            SmoothingMethod::Lloyd => self.voronoi_centroid(point, position),
+        };
+        Some(target.unwrap_or(uniform))
+    }
+
+    fn area_weighted_target(&self, point: PointId) -> Option<Point2d> {
+        let (total, sum) = self
+            .triangles_around(point)
+            .into_iter()
+            .filter_map(|triangle| self.triangles.get(triangle))
+            .filter_map(|triangle| {
+                self.triangle_quality(triangle)
+                    .zip(self.triangle_positions(triangle))
+            })
+            .fold(
+                (0_f32, Point2d::default()),
+                |(total, sum), ((_, area), [p0, p1, p2])| {
+                    (total + area, sum + (p0 + p1 + p2) * (area / 3_f32))
+                },
+            );
+        (total > f32::EPSILON).then(|| sum / total)
+    }
+
+    // the voronoi cell is the loop of the circumcenters of the triangles around the point
+    fn voronoi_centroid(&self, point: PointId, position: Point2d) -> Option<Point2d> {
+        let cell = self
+            .triangles_around(point)
+            .into_iter()
+            .filter_map(|triangle| self.triangles.get(triangle))
+            .filter_map(|triangle| self.triangle_positions(triangle))
+            .map(|[p0, p1, p2]| Point2d::circumcenter(&p0, &p1, &p2))
+            .sorted_by(|a, b| {
+                let a = (a.y - position.y).atan2(a.x - position.x);
+                let b = (b.y - position.y).atan2(b.x - position.x);
+                f32::total_cmp(&a, &b)
+            })
+            .collect_vec();
+        if cell.len() < 3 {
+            return None;
+        }
+
+        let (area, centroid) = cell.iter().circular_tuple_windows().fold(
+            (0_f32, Point2d::default()),
+            |(area, centroid), (a, b)| {
+                let cross = a.x * b.y - b.x * a.y;
+                (area + cross, centroid + (*a + *b) * cross)
+            },
+        );
+        (area.abs() > f32::EPSILON).then(|| centroid / (area * 3_f32))
+    }
+
+    // moves the point towards the target, halving the step until none of its triangles fold
+    fn relax_point(&mut self, point: PointId, target: Point2d, strength: f32) {
+        let Some(from) = self.points.get(point).copied() else {
+            return;
+        };
+        let triangles = self.triangles_around(point);
+        let mut step = strength;
+
+        for _ in 0..4 {
+            let to = from + (target - from) * step;
+            step /= 2_f32;
+
+            let folds = triangles
+                .iter()
+                .filter_map(|triangle| self.triangles.get(*triangle))
+                .filter_map(|triangle| self.triangle_positions(triangle).zip(Some(triangle)))
+                .any(|(mut positions, triangle)| {
+                    for (position, id) in positions.iter_mut().zip(triangle.points()) {
+                        if id == point {
+                            *position = to;
+                        }
+                    }
+                    let [p0, p1, p2] = positions;
+                    !Point2d::orientation(&p0, &p1, &p2).is_counter_clock_wise()
+                });
+            if folds {
+                continue;
+            }
+
+            let uv = triangles.iter().find_map(|triangle| {
+                let real_triangle = self.triangles.get(*triangle)?;
+                let [p0, p1, p2] = self.triangle_positions(real_triangle)?;
+                let weights = to.barycentric(&p0, &p1, &p2)?;
+                if weights.iter().any(|weight| *weight < 0_f32) {
+                    return None;
+                }
+                self.interpolate_uv(to, Located::InTriangle(*triangle))
+            });
+            self.move_point(point, to);
+            if uv.is_some() {
+                self.put_uv(point, uv);
+            }
+            return;
+        }
+    }
+
+    // removes the triangles around every hole, and outside of the constraints if set
+    fn carve(&mut self) {
+        let mut seeds = vec![];
+
+        for hole in self.holes.clone() {
+            match self.locate(hole) {
+                Located::InTriangle(triangle) => seeds.push(triangle),
+                Located::OnEdge(edge) => {
+                    if let Some(edge) = self.edges.get(edge) {
+                        seeds.extend([edge.triangle0, edge.triangle1].into_iter().flatten());
+                    }
+                }
+                Located::OnVertex(_) | Located::Outside => {}
+            }
+        }
+
+        if self.carve_exterior {
+            for edge in &self.boarder_edges {
+                if let Some(edge) = self.edges.get(*edge) {
+                    if !edge.locked {
+                        seeds.extend(edge.triangle0.or(edge.triangle1));
+                    }
+                }
+            }
+        }
+
+        while let Some(triangle) = seeds.pop() {
+            let Some(triangle_edges) = self.triangle_edges.get(triangle) else {
+                continue;
+            };
+
+            for edge in triangle_edges.edges {
+                if let Some(edge) = self.edges.get(edge) {
+                    if !edge.locked {
+                        seeds.extend(edge.other_triangle(triangle));
+                    }
+                }
+            }
+
+            if let Some((_, edges)) = self.disconnect_triangle(triangle) {
+                self.prune_edges(edges.edges);
+            }
+        }
+    }
+
+    // connects an already inserted point to the mesh at where it was located
+    fn triangulate_point(&mut self, id: PointId, located: Located) -> NCResult<()> {
+        match located {
+            Located::InTriangle(triangle) => self.split_triangle_at(triangle, id),
+            Located::OnEdge(edge) => {
+                let to_legalize = self.split_edge_at(edge, id)?;
+                self.legalize(to_legalize);
+                Ok(())
+            }
+            Located::OnVertex(_) => Err(Nari2DCoreError::AlreadyExists(
+                self.points.get(id).copied().unwrap_or_default(),
+            )),
+            Located::Outside => self.connect_outside(id),
+        }
+    }
+
+    // finds the triangle, edge or point at the point by walking over the triangles from the
+    // nearest point. falls back to checking every triangle if the walk gets stuck on the boarder,
+    // which happens for points outside or across a concave part of the mesh.
+    #[must_use]
+    pub fn locate(&self, point: Point2d) -> Located {
+        match self
+            .nearest_triangle(point)
+            .and_then(|start| self.walk(start, point))
+        {
+            Some(Located::Outside) | None => self.locate_brute(point),
+            Some(located) => located,
+        }
+    }
+
+    // visibility walk from `start` towards the point, never stepping back over the edge it came
+    // from. returns Outside when the boarder is in the way and None if it took too long, which
+    // can only happen on meshes that are far from delaunay
+    fn walk(&self, start: TriangleId, point: Point2d) -> Option<Located> {
+        let mut current = start;
+        let mut came_from = None;
+
+        for step in 0..=self.triangles.len() {
+            let triangle = self.triangles.get(current)?;
+            let edges = self.triangle_edges.get(current)?;
+            let positions = self.triangle_positions(triangle)?;
+            let [p0, p1, p2] = positions;
+            let orientations = [
+                Point2d::orientation(&p0, &p1, &point),
+                Point2d::orientation(&p1, &p2, &point),
+                Point2d::orientation(&p2, &p0, &point),
+            ];
+
+            // rotate the side that is tried first, so the walk can't circle around forever
+            let across = (0..3).map(|side| (side + step) % 3).find(|side| {
+                orientations[*side].is_clockwise() && Some(edges.edges[*side]) != came_from
+            });
+
+            let Some(side) = across else {
+                return Some(Self::located_in(
+                    current,
+                    triangle,
+                    edges,
+                    positions,
+                    orientations,
+                    point,
+                ));
+            };
+            let edge = edges.edges[side];
+            match self.edges.get(edge)?.other_triangle(current) {
+                Some(next) => {
+                    came_from = Some(edge);
+                    current = next;
+                }
+                None => return Some(Located::Outside),
+            }
+        }
+
+        None
+    }
+
+    // classifies a point that is not outside of any side of the triangle
+    fn located_in(
+        id: TriangleId,
+        triangle: &Triangle,
+        edges: &TriangleEdge,
+        positions: [Point2d; 3],
+        orientations: [Orientation; 3],
+        point: Point2d,
+    ) -> Located {
+        match orientations.iter().filter(|o| o.is_colinear()).count() {
+            0 => Located::InTriangle(id),
+            1 => orientations
+                .iter()
+                .position(Orientation::is_colinear)
+                .map_or(Located::InTriangle(id), |side| {
+                    Located::OnEdge(edges.edges[side])
+                }),
+            _ => triangle
+                .points()
+                .into_iter()
+                .zip(positions)
+                .min_by(|(_, a), (_, b)| f32::total_cmp(&a.distance2(&point), &b.distance2(&point)))
+                .map_or(Located::InTriangle(id), |(vertex, _)| {
+                    Located::OnVertex(vertex)
+                }),
+        }
+    }
+
+    // a triangle around the nearest point that has any
+    fn nearest_triangle(&self, point: Point2d) -> Option<TriangleId> {
+        self.index
+            .nearest_neighbor_iter(&point)
+            .find_map(|nearest| self.any_triangle(nearest.data))
+    }
+
+    fn any_triangle(&self, point: PointId) -> Option<TriangleId> {
+        self.point_edges
+            .get(point)?
+            .edges
+            .iter()
+            .filter_map(|edge| self.edges.get(*edge))
+            .find_map(|edge| edge.triangle0.or(edge.triangle1))
+    }
+
+    fn locate_brute(&self, point: Point2d) -> Located {
+        for (id, triangle) in &self.triangles {
+            let positions = some_or_continue!(self.triangle_positions(triangle));
+            let edges = some_or_continue!(self.triangle_edges.get(id));
+            let [p0, p1, p2] = positions;
+            let orientations = [
+                Point2d::orientation(&p0, &p1, &point),
+                Point2d::orientation(&p1, &p2, &point),
+                Point2d::orientation(&p2, &p0, &point),
+            ];
+
+            if !orientations.iter().any(Orientation::is_clockwise) {
+                return Self::located_in(id, triangle, edges, positions, orientations, point);
+            }
+        }
+
+        Located::Outside
+    }
+
+    fn split_triangle_at(&mut self, triangle: TriangleId, point: PointId) -> NCResult<()> {
+        let (old, old_edges) = self
+            .disconnect_triangle(triangle)
+            .ok_or_else(|| Nari2DCoreError::DoesNotExist(format!("Triangle {triangle}").into()))?;
+        let [p0, p1, p2] = old.points();
+
+        self.connect_triangle([p0, p1, point].into())?;
+        self.connect_triangle([p1, p2, point].into())?;
+        self.connect_triangle([p2, p0, point].into())?;
+        self.prune_edges(old_edges.edges);
+        self.legalize(vec![(p0, p1), (p1, p2), (p2, p0)]);
+        Ok(())
+    }
+
+    // splits the edge and its triangles at the point. returns the outer edges of the new
+    // triangles, which may need to be legalized.
+    fn split_edge_at(&mut self, edge: EdgeId, point: PointId) -> NCResult<Vec<(PointId, PointId)>> {
+        let real_edge = *self
+            .edges
+            .get(edge)
+            .ok_or_else(|| Nari2DCoreError::DoesNotExist(format!("Edge {edge}").into()))?;
+
+        let mut fans = Vec::with_capacity(2);
+        for triangle in [real_edge.triangle0, real_edge.triangle1]
+            .into_iter()
+            .flatten()
+        {
+            let rotated = self
+                .triangles
+                .get(triangle)
+                .and_then(|tri| {
+                    tri.rotated_to(real_edge.point0, real_edge.point1)
+                        .or_else(|| tri.rotated_to(real_edge.point1, real_edge.point0))
+                })
+                .ok_or(Nari2DCoreError::NonManifoldStructure)?;
+            fans.push((triangle, rotated));
+        }
+
+        let mut to_legalize = Vec::with_capacity(4);
+        let mut old_edges = vec![];
+        for (triangle, _) in &fans {
+            if let Some((_, edges)) = self.disconnect_triangle(*triangle) {
+                old_edges.extend(edges.edges);
+            }
+        }
+        for (_, rotated) in fans {
+            let [from, to, opposite] = rotated.points();
+            self.connect_triangle([from, point, opposite].into())?;
+            self.connect_triangle([point, to, opposite].into())?;
+            to_legalize.push((to, opposite));
+            to_legalize.push((opposite, from));
+        }
+        // both halves of a constraint stay constraints
+        if real_edge.locked {
+            for (a, b) in [(real_edge.point0, point), (point, real_edge.point1)] {
+                if let Some(half) = self.edge_from_points(a, b).copied() {
+                    self.set_edge_locked(half, true)?;
+                }
+            }
+        }
+        self.prune_edges(old_edges);
+        Ok(to_legalize)
+    }
+
+    // connects a point outside of the mesh to every boarder edge it can see.
+    fn connect_outside(&mut self, point: PointId) -> NCResult<()> {
+        let position = *self
+            .points
+            .get(point)
+            .ok_or_else(|| Nari2DCoreError::DoesNotExist(format!("Point {point}").into()))?;
+
+        let visible = self
+            .boarder_edges
+            .iter()
+            .filter_map(|edge| {
+                let (from, to) = self.boarder_edge_winding(*edge)?;
+                let orientation =
+                    Point2d::orientation(self.points.get(from)?, self.points.get(to)?, &position);
+                (orientation.is_clockwise() && !self.is_occluded(*edge, from, to, position))
+                    .then_some((from, to))
+            })
+            .collect_vec();
+
+        if visible.is_empty() {
+            return Err(Nari2DCoreError::Triangulation(
+                format!("Point {position} cannot see the mesh boarder").into(),
+            ));
+        }
+
+        for (from, to) in &visible {
+            self.connect_triangle([*to, *from, point].into())?;
+        }
+        self.legalize(visible);
+        Ok(())
+    }
+
+    // true if the triangle between the boarder edge and the point would overlap the mesh, which
+    // only happens when the boarder is not convex
+    fn is_occluded(&self, edge: EdgeId, from: PointId, to: PointId, point: Point2d) -> bool {
+        let (Some(from_position), Some(to_position)) = (self.points.get(from), self.points.get(to))
+        else {
+            return true;
+        };
+
+        self.boarder_edges
+            .iter()
+            .filter(|other| **other != edge)
+            .filter_map(|other| self.edges.get(*other))
+            .any(|other| {
+                if self.crosses(other, point, *from_position)
+                    || self.crosses(other, point, *to_position)
+                {
+                    return true;
+                }
+                [other.point0, other.point1]
+                    .into_iter()
+                    .filter(|vertex| *vertex != from && *vertex != to)
+                    .filter_map(|vertex| self.points.get(vertex))
+                    .any(|vertex| {
+                        let sides = [
+                            Point2d::orientation(to_position, from_position, vertex),
+                            Point2d::orientation(from_position, &point, vertex),
+                            Point2d::orientation(&point, to_position, vertex),
+                        ];
+                        // inside, or on one of the new edges
+                        !sides.iter().any(Orientation::is_clockwise) && !sides[0].is_colinear()
+                    })
+            })
+    }
+
+    // returns the boarder edge's points ordered so that the mesh is on the left
+    fn boarder_edge_winding(&self, edge: EdgeId) -> Option<(PointId, PointId)> {
+        let real_edge = self.edges.get(edge)?;
+        let triangle = self
+            .triangles
+            .get(real_edge.triangle0.or(real_edge.triangle1)?)?;
+        let rotated = triangle
+            .rotated_to(real_edge.point0, real_edge.point1)
+            .or_else(|| triangle.rotated_to(real_edge.point1, real_edge.point0))?;
+        Some((rotated.point0, rotated.point1))
+    }
+
+    // flips edges until every edge reachable from `edges` is locally delaunay
+    fn legalize(&mut self, mut edges: Vec<(PointId, PointId)>) {
+        while let Some((a, b)) = edges.pop() {
+            let edge = *some_or_continue!(self.edge_from_points(a, b));
+            if !self.is_illegal(edge) {
+                continue;
+            }
+            let (from, to, left, right) = some_or_continue!(self.edge_quad(edge));
+            if self.flip(edge).is_some() {
+                edges.push((from, right));
+                edges.push((right, to));
+                edges.push((to, left));
+                edges.push((left, from));
+            }
+        }
+    }
+
+    fn is_illegal(&self, edge: EdgeId) -> bool {
+        if self.edges.get(edge).is_none_or(|e| e.locked) {
+            return false;
+        }
+        let Some((from, to, left, right)) = self.edge_quad(edge) else {
+            return false;
+        };
+
+        match (
+            self.points.get(from),
+            self.points.get(to),
+            self.points.get(left),
+            self.points.get(right),
+        ) {
+            (Some(from), Some(to), Some(left), Some(right)) => {
+                right.in_circumcircle(from, to, left)
+            }
+            _ => false,
+        }
+    }
+
+    // for an edge with two triangles, returns (from, to, left, right) where (from, to, left) and
+    // (to, from, right) are the two triangles in counter clockwise order.
+    fn edge_quad(&self, edge: EdgeId) -> Option<(PointId, PointId, PointId, PointId)> {
+        let real_edge = self.edges.get(edge)?;
+        let first = self.triangles.get(real_edge.triangle0?)?;
+        let second = self.triangles.get(real_edge.triangle1?)?;
+        let left = first
+            .rotated_to(real_edge.point0, real_edge.point1)
+            .or_else(|| first.rotated_to(real_edge.point1, real_edge.point0))?;
+        let right = second.rotated_to(left.point1, left.point0)?;
+        Some((left.point0, left.point1, left.point2, right.point2))
+    }
+
+    // replaces the edge between two triangles with the other diagonal of their quad.
+    // does nothing and returns None if the quad is not strictly convex.
+    fn flip(&mut self, edge: EdgeId) -> Option<EdgeId> {
+        let (from, to, left, right) = self.edge_quad(edge)?;
+        let real_edge = *self.edges.get(edge)?;
+
+        let p_from = self.points.get(from)?;
+        let p_to = self.points.get(to)?;
+        let p_left = self.points.get(left)?;
+        let p_right = self.points.get(right)?;
+        if !(Point2d::orientation(p_from, p_right, p_left).is_counter_clock_wise()
+            && Point2d::orientation(p_right, p_to, p_left).is_counter_clock_wise())
+        {
+            return None;
+        }
+
+        let (_, first_edges) = self.disconnect_triangle(real_edge.triangle0?)?;
+        let (_, second_edges) = self.disconnect_triangle(real_edge.triangle1?)?;
+        // neither can fail: both triangles are strictly convex and the old diagonal is gone
+        self.connect_triangle([from, right, left].into()).ok()?;
+        self.connect_triangle([right, to, left].into()).ok()?;
+        self.prune_edges(first_edges.edges.into_iter().chain(second_edges.edges));
+        self.edge_from_points(left, right).copied()
+    }
+
+    // inserts the triangle, creating and linking its edges as needed.
+    fn connect_triangle(&mut self, triangle: Triangle) -> NCResult<TriangleId> {
+        let points = triangle.points();
+        let mut existing = [None; 3];
+
+        for (side, existing) in existing.iter_mut().enumerate() {
+            let (a, b) = (points[side], points[(side + 1) % 3]);
+            if !self.points.contains_key(a) {
+                return Err(Nari2DCoreError::DoesNotExist(format!("Point {a}").into()));
+            }
+            if let Some(edge) = self.edge_from_points(a, b) {
+                match self.edges.get(*edge) {
+                    Some(real_edge) if real_edge.triangle_count() < 2 => *existing = Some(*edge),
+                    _ => return Err(Nari2DCoreError::NonManifoldStructure),
+                }
+            }
+        }
+
+        let triangle_id = self.triangles.insert(triangle);
+        let mut triangle_edges = [EdgeId::default(); 3];
+
+        for (side, edge) in existing.into_iter().enumerate() {
+            let (a, b) = (points[side], points[(side + 1) % 3]);
+            let edge_id = if let Some(edge_id) = edge {
+                edge_id
+            } else {
+                let edge_id = self.insert_edge(Edge {
+                    point0: a,
+                    point1: b,
+                    triangle0: None,
+                    triangle1: None,
+                    locked: false,
+                });
+                self.add_pointedge_or_init(a, edge_id);
+                self.add_pointedge_or_init(b, edge_id);
+                edge_id
+            };
+            if let Some(real_edge) = self.edges.get_mut(edge_id) {
+                real_edge.add_triangle_unoccupied(triangle_id);
+            }
+            self.update_boarder_edge(edge_id);
+            triangle_edges[side] = edge_id;
+        }
+
+        self.triangle_edges
+            .insert(triangle_id, TriangleEdge::from(triangle_edges));
+        // after the edges, so taking it back removes the triangle before them
+        self.log(Change::ConnectTriangle(triangle_id));
+        Ok(triangle_id)
+    }
+
+    // removes the triangle, unlinking it from its edges. edges left without a triangle are kept
+    // (and keep their id and lock) so the caller can reuse them, see `prune_edges`.
+    fn disconnect_triangle(&mut self, id: TriangleId) -> Option<(Triangle, TriangleEdge)> {
+        let triangle = self.triangles.remove(id)?;
+        let triangle_edges = self.triangle_edges.remove(id).unwrap_or_default();
+        self.log(Change::DisconnectTriangle { id, triangle });
+
+        for edge in triangle_edges.edges {
+            if let Some(real_edge) = self.edges.get_mut(edge) {
+                real_edge.remove_triangle(id);
+                self.update_boarder_edge(edge);
+            }
+        }
+
+        Some((triangle, triangle_edges))
+    }
+
+    // removes the edges that are not part of any triangle anymore
+    fn prune_edges(&mut self, edges: impl IntoIterator<Item = EdgeId>) {
+        for edge in edges {
+            if self
+                .edges
+                .get(edge)
+                .is_some_and(|e| e.triangle_count() == 0)
+            {
+                self.disconnect_edge(edge);
+            }
+        }
+    }
+
+    fn disconnect_edge(&mut self, id: EdgeId) -> Option<Edge> {
+        let edge = self.edges.remove(id)?;
+        self.log(Change::RemoveEdge {
+            id,
+            points: (edge.point0, edge.point1),
+            locked: edge.locked,
+        });
+        for point in [edge.point0, edge.point1] {
+            if let Some(point_edge) = self.point_edges.get_mut(point) {
+                point_edge.edges.retain(|e| *e != id);
+            }
+        }
+        self.boarder_edges.retain(|e| *e != id);
+        Some(edge)
+    }
+
+    fn update_boarder_edge(&mut self, id: EdgeId) {
+        let is_boarder = self.edges.get(id).is_some_and(Edge::is_boarder);
+        let listed = self.boarder_edges.contains(&id);
+
+        if is_boarder && !listed {
+            self.boarder_edges.push(id);
+        } else if !is_boarder && listed {
+            self.boarder_edges.retain(|e| *e != id);
+        }
+    }
+
+    fn triangle_positions(&self, triangle: &Triangle) -> Option<[Point2d; 3]> {
+        Some([
+            *self.points.get(triangle.point0)?,
+            *self.points.get(triangle.point1)?,
+            *self.points.get(triangle.point2)?,
+        ])
+    }
+
+    // the nearest point that is not on top of `point`
+    pub fn closest(&self, point: &Point2d) -> Option<PointId> {
+        self.index
+            .nearest_neighbor_iter(point)
+            .find(|nearest| nearest.geom().distance2(point) > 0_f32)
+            .map(|nearest| nearest.data)
+    }
+
+    pub fn closest_n(&self, point: &Point2d, n: u32) -> Option<Vec<PointId>> {
+        if self.points.len() == 0 {
+            return None;
+        }
+
+        Some(
+            self.index
+                .nearest_neighbor_iter(point)
+                .take(n as usize)
+                .map(|nearest| nearest.data)
+                .collect_vec(),
+        )
+    }
+
+    // andrew's monotone chain. counter clockwise, without colinear points
+    #[must_use]
+    pub fn convex_hull(&self) -> Vec<PointId> {
+        let sorted = self
+            .points
+            .iter()
+            .sorted_by(|(_, a), (_, b)| a.cmp(b))
+            .dedup_by(|(_, a), (_, b)| a == b)
+            .collect_vec();
+        if sorted.len() < 3 {
+            return sorted.into_iter().map(|(id, _)| id).collect();
+        }
+
+        let chain = |points: &mut dyn Iterator<Item = &(PointId, &Point2d)>| {
+            let mut chain: Vec<(PointId, Point2d)> = vec![];
+            for (id, point) in points {
+                while let [.., (_, a), (_, b)] = chain[..] {
+                    if Point2d::orientation(&a, &b, point).is_counter_clock_wise() {
+                        break;
+                    }
+                    chain.pop();
+                }
+                chain.push((*id, **point));
+            }
+            // the last point starts the other chain
+            chain.pop();
+            chain
+        };
+
+        let mut hull = chain(&mut sorted.iter());
+        hull.extend(chain(&mut sorted.iter().rev()));
+        hull.into_iter().map(|(id, _)| id).collect()
+    }
+
+    // https://github.com/mourner/delaunator-rs/blob/master/src/lib.rs
+    pub fn bbox_center(&self) -> Point2d {
+        let mut min_x = f32::INFINITY;
+        let mut min_y = f32::INFINITY;
+        let mut max_x = f32::NEG_INFINITY;
+        let mut max_y = f32::NEG_INFINITY;
+
+        for point in self.points.values() {
+            min_x = min_x.min(point.x);
+            min_y = min_y.min(point.y);
+            max_x = max_x.max(point.x);
+            max_y = max_y.max(point.y);
+        }
+
         Point2d::new((min_x + max_x) / 2_f32, (min_y + max_y) / 2_f32)
     }
 
@@ -634,7 +2729,10 @@ impl EditMesh {
         let mut idx_c = PointId::default();
 
         for (idx, pt) in self.points.iter() {
-            if idx == idx_a || idx == idx_b {
+            if idx == idx_a
+                || idx == idx_b
+                || Point2d::orientation(a_point, b_point, pt).is_colinear()
+            {
                 continue;
             }
 
@@ -658,28 +2756,96 @@ impl EditMesh {
         }
     }
 
-    fn visible_edge(&self, edges: &[EdgeId], point: &Point2d) -> Option<EdgeId> {
-        todo!()
+    // starts logging every change, replacing what was logged so far
+    pub(crate) fn record(&mut self) {
+        self.changes = Some(vec![]);
     }
 
-    fn point_inside_polygon(&self, point: &Point2d) -> bool {
-        // turn the edges into points
-        let point_a_gon = match self
-            .boarder_edges
-            .iter()
-            .map(|x| {
-                self.edges
-                    .get(*x)
-                    .map(|edge| self.points.get(edge.point0).map(|pt| *pt))
-                    .flatten()
-            })
-            .collect::<Option<Vec<Point2d>>>()
-        {
-            None => return false,
-            Some(v) => v,
+    // stops logging, returning what was logged in the order it happened
+    pub(crate) fn take_changes(&mut self) -> Vec<Change> {
+        self.changes.take().unwrap_or_default()
+    }
+
+    // runs the edit, taking back everything it changed if it fails. the changes and what took
+    // them back are both logged, as triangles and edges that were put back have new ids
+    fn or_revert<T>(&mut self, edit: impl FnOnce(&mut Self) -> NCResult<T>) -> NCResult<T> {
+        let outer = self.changes.replace(vec![]);
+        let result = edit(self);
+        let mut changes = self.changes.replace(vec![]).unwrap_or_default();
+        let reverted = if result.is_err() {
+            self.revert(&changes, &mut Remap::default())
+        } else {
+            Ok(())
         };
+        changes.extend(self.changes.take().unwrap_or_default());
+        self.changes = outer.map(|mut log| {
+            log.extend(changes);
+            log
+        });
+        reverted.and(result)
+    }
+
+    fn log(&mut self, change: Change) {
+        if let Some(changes) = &mut self.changes {
+            changes.push(change);
+        }
+    }
 
-        point.is_inside(&point_a_gon)
+    // takes the changes back, last first. whatever is put back gets a new id, which is added
+    // to `remap`
+    pub(crate) fn revert(&mut self, changes: &[Change], remap: &mut Remap) -> NCResult<()> {
+        for change in changes.iter().rev() {
+            match change {
+                Change::InsertPoint(id) => {
+                    let id = remap.point(*id);
+                    self.point_edges.remove(id);
+                    self.forget_point(id);
+                }
+                Change::RemovePoint { id, position, uv } => {
+                    let restored = self.insert_point(*position);
+                    if uv.is_some() {
+                        self.put_uv(restored, *uv);
+                    }
+                    remap.restore_point(*id, restored);
+                }
+                Change::MovePoint { id, from } => self.move_point(remap.point(*id), *from),
+                Change::SetUv { id, from } => self.put_uv(remap.point(*id), *from),
+                Change::InsertEdge(id) => {
+                    self.disconnect_edge(remap.edge(*id));
+                }
+                Change::RemoveEdge {
+                    id,
+                    points: (a, b),
+                    locked,
+                } => {
+                    let (a, b) = (remap.point(*a), remap.point(*b));
+                    let restored = self.insert_edge(Edge {
+                        point0: a,
+                        point1: b,
+                        triangle0: None,
+                        triangle1: None,
+                        locked: *locked,
+                    });
+                    self.add_pointedge_or_init(a, restored);
+                    self.add_pointedge_or_init(b, restored);
+                    remap.restore_edge(*id, restored);
+                }
+                Change::LockEdge { id, was } => self.set_edge_locked(remap.edge(*id), *was)?,
+                Change::ConnectTriangle(id) => {
+                    self.disconnect_triangle(remap.triangle(*id));
+                }
+                Change::DisconnectTriangle { id, triangle } => {
+                    let points = triangle.points().map(|point| remap.point(point));
+                    let restored = self.connect_triangle(points.into())?;
+                    remap.restore_triangle(*id, restored);
+                }
+                Change::SetHoles {
+                    holes,
+                    carve_exterior,
+                } => self.set_holes(holes.clone(), *carve_exterior),
+            }
+        }
+        Ok(())
     }
 
     fn check_if_point_exists(&mut self, point: Point2d) -> bool {
@@ -691,3 +2857,301 @@ impl EditMesh {
         return true;
     }
 }
+
+impl MeshImplementation for EditMesh {
+    fn vertex_count(&self) -> usize {
+        self.points.len()
+    }
+
+    fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    fn triangle_vertices(&self) -> impl Iterator<Item = [Point2d; 3]> + '_ {
+        self.triangles
+            .values()
+            .filter_map(|triangle| self.triangle_positions(triangle))
+    }
+
+    fn bounds(&self) -> Bounds {
+        Bounds::from_points(self.points.values().copied()).unwrap_or_default()
+    }
+}
+
+// the angle turning clockwise from `from` to `to`, in [0, 2pi)
+fn clockwise_angle(from: Point2d, to: Point2d) -> f32 {
+    let cross = to.x * from.y - to.y * from.x;
+    let dot = to.x * from.x + to.y * from.y;
+    f32::atan2(cross, dot).rem_euclid(std::f32::consts::TAU)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a triangulated grid of `size` by `size` points, a unit apart
+    fn grid(size: u8) -> EditMesh {
+        let mut mesh = EditMesh::new();
+        for i in 0..size * size {
+            mesh.insert_point(Point2d::new(f32::from(i % size), f32::from(i / size)));
+        }
+        mesh.retriangulate_mesh().unwrap();
+        mesh
+    }
+
+    // the point exactly at x, y. `closest` skips a point right on top of the query
+    fn point_at(mesh: &EditMesh, x: f32, y: f32) -> PointId {
+        mesh.points()
+            .find(|(_, point)| **point == Point2d::new(x, y))
+            .map(|(id, _)| id)
+            .unwrap()
+    }
+
+    // an edge with neither point on the boarder
+    fn interior_edge(mesh: &EditMesh) -> EdgeId {
+        mesh.edges()
+            .find(|(_, edge)| !mesh.is_pinned(edge.point0) && !mesh.is_pinned(edge.point1))
+            .map(|(id, _)| id)
+            .unwrap()
+    }
+
+    // grid(5) with a locked square around the cell at (1, 1), carved out as a hole, and one more
+    // locked edge across the cell at (2, 2)
+    fn holed() -> EditMesh {
+        let mut mesh = grid(5);
+        let square =
+            [(1.0, 1.0), (2.0, 1.0), (2.0, 2.0), (1.0, 2.0)].map(|(x, y)| point_at(&mesh, x, y));
+        let mut constraints = square.into_iter().circular_tuple_windows().collect_vec();
+        constraints.push((point_at(&mesh, 2.0, 3.0), point_at(&mesh, 3.0, 2.0)));
+        mesh.retriangulate_constrained(&constraints, &[Point2d::new(1.5, 1.5)], false)
+            .unwrap();
+        mesh
+    }
+
+    // what a round trip has to keep: the points in order, how many triangles there are, the
+    // locked edges between them and the kinds of boarder loops
+    fn summary(mesh: &EditMesh) -> (Vec<Point2d>, usize, Vec<[usize; 2]>, Vec<LoopKind>) {
+        let ids = mesh.points().map(|(id, _)| id).collect_vec();
+        let index = |id: PointId| ids.iter().position(|other| *other == id).unwrap();
+        let locked = mesh
+            .edges()
+            .filter(|(_, edge)| edge.locked)
+            .map(|(_, edge)| {
+                let (a, b) = (index(edge.point0), index(edge.point1));
+                [a.min(b), a.max(b)]
+            })
+            .sorted()
+            .collect();
+        let kinds = mesh
+            .boarder_loops()
+            .into_iter()
+            .map(|boarder_loop| boarder_loop.kind)
+            .sorted_by_key(|kind| *kind == LoopKind::Hole)
+            .collect();
+        (
+            mesh.points().map(|(_, point)| *point).collect(),
+            mesh.triangles().count(),
+            locked,
+            kinds,
+        )
+    }
+
+    #[test]
+    fn closest_never_returns_a_removed_point() {
+        let mut mesh = EditMesh::new();
+        let ids = (0..16_u8)
+            .map(|i| mesh.insert_point(Point2d::new(f32::from(i % 4), f32::from(i / 4))))
+            .collect_vec();
+        let removed = ids[5];
+        mesh.set_uv(removed, Point2d::new(0.5, 0.5)).unwrap();
+        mesh.remove_point(removed).unwrap();
+
+        assert!(mesh.uv(removed).is_none());
+        let query = Point2d::new(1.1, 1.1);
+        assert_ne!(mesh.closest(&query), Some(removed));
+        assert!(!mesh.closest_n(&query, 16).unwrap().contains(&removed));
+    }
+
+    #[test]
+    fn remove_point_leaves_mesh_alone_when_the_constraint_cannot_be_rebuilt() {
+        let mut mesh = EditMesh::new();
+        for corner in [(-2.0, -3.0), (6.0, -3.0), (6.0, 4.0), (-2.0, 4.0)] {
+            mesh.insert_point(Point2d::new(corner.0, corner.1));
+        }
+        let a = mesh.insert_point(Point2d::new(0.0, 0.0));
+        let middle = mesh.insert_point(Point2d::new(2.0, 2.0));
+        let b = mesh.insert_point(Point2d::new(4.0, 0.0));
+        let c = mesh.insert_point(Point2d::new(2.0, 1.0));
+        let d = mesh.insert_point(Point2d::new(2.0, -1.0));
+        mesh.retriangulate_constrained(&[(a, middle), (middle, b), (c, d)], &[], false)
+            .unwrap();
+        let triangles = mesh.triangles().count();
+        let around = mesh.triangles_around(middle).len();
+
+        // a - b would cross c - d
+        assert!(mesh.remove_point(middle).is_err());
+        mesh.validate().unwrap();
+        assert_eq!(mesh.point(middle), Some(&Point2d::new(2.0, 2.0)));
+        assert_eq!(mesh.triangles().count(), triangles);
+        assert_eq!(mesh.triangles_around(middle).len(), around);
+        for (from, to) in [(a, middle), (middle, b), (c, d)] {
+            let edge = mesh.edge_from_points(from, to).unwrap();
+            assert!(mesh.edge(*edge).unwrap().locked);
+        }
+    }
+
+    #[test]
+    fn refine_never_adds_more_than_max_steiner_points() {
+        for cap in 0..24 {
+            let mut mesh = EditMesh::new();
+            for point in [(0.0, 0.0), (12.0, 0.0), (12.0, 1.0), (0.0, 1.0), (6.0, 0.4)] {
+                mesh.insert_point(Point2d::new(point.0, point.1));
+            }
+            mesh.retriangulate_mesh().unwrap();
+            let before = mesh.points().count();
+
+            let report = mesh
+                .refine(RefineOptions {
+                    max_area: Some(0.05),
+                    max_steiner_points: cap,
+                    ..RefineOptions::default()
+                })
+                .unwrap();
+
+            assert!(
+                report.points_added <= cap,
+                "added {} over {cap}",
+                report.points_added
+            );
+            assert_eq!(mesh.points().count(), before + report.points_added);
+        }
+    }
+
+    #[test]
+    fn remove_point_keeps_the_mesh_valid() {
+        let mut mesh = grid(5);
+
+        // inside, on the boarder and in a corner
+        for (x, y) in [(2.0, 2.0), (2.0, 0.0), (4.0, 4.0)] {
+            let point = point_at(&mesh, x, y);
+            mesh.remove_point(point).unwrap();
+            mesh.validate().unwrap();
+            assert!(mesh.point(point).is_none());
+        }
+        assert_eq!(mesh.points().count(), 22);
+    }
+
+    #[test]
+    fn split_and_collapse_keep_the_mesh_valid() {
+        let mut mesh = grid(5);
+        let triangles = mesh.triangles().count();
+
+        let split = mesh.split_edge(interior_edge(&mesh)).unwrap();
+        mesh.validate().unwrap();
+        assert!(mesh.point(split).is_some());
+        assert_eq!(mesh.triangles().count(), triangles + 2);
+
+        let kept = mesh.collapse_edge(interior_edge(&mesh)).unwrap();
+        mesh.validate().unwrap();
+        assert!(mesh.point(kept).is_some());
+        assert_eq!(mesh.triangles().count(), triangles);
+    }
+
+    #[test]
+    fn refine_keeps_the_mesh_valid() {
+        let mut mesh = EditMesh::new();
+        for point in [(0.0, 0.0), (8.0, 0.0), (8.0, 1.0), (0.0, 1.0), (3.0, 0.3)] {
+            mesh.insert_point(Point2d::new(point.0, point.1));
+        }
+        mesh.retriangulate_mesh().unwrap();
+
+        let report = mesh
+            .refine(RefineOptions {
+                max_area: Some(0.1),
+                ..RefineOptions::default()
+            })
+            .unwrap();
+
+        mesh.validate().unwrap();
+        assert!(report.points_added > 0);
+        assert!(report.unfixed.is_empty());
+    }
+
+    #[test]
+    fn failed_insertion_leaves_the_mesh_alone() {
+        let mut mesh = grid(3);
+        let triangles = mesh.triangles().count();
+        let bottom = mesh
+            .edges()
+            .find(|(_, edge)| {
+                edge.is_boarder()
+                    && [edge.point0, edge.point1]
+                        .iter()
+                        .all(|point| mesh.point(*point).is_some_and(|pos| pos.y == 0_f32))
+            })
+            .map(|(id, _)| id)
+            .unwrap();
+        // listed twice, the point below is connected to it twice and the second one fails after
+        // the first triangles are already in
+        mesh.boarder_edges.push(bottom);
+
+        assert!(mesh
+            .insert_point_with_connectivity(Point2d::new(1.0, -1.0))
+            .is_none());
+        mesh.validate().unwrap();
+        assert_eq!(mesh.points().count(), 9);
+        assert_eq!(mesh.triangles().count(), triangles);
+        assert_eq!(mesh.boarder_edges().len(), 8);
+    }
+
+    #[test]
+    fn remove_point_keeps_constraints_that_end_at_it() {
+        let mut mesh = grid(5);
+        let center = point_at(&mesh, 2.0, 2.0);
+        let ends = [(0.0, 2.0), (4.0, 2.0), (2.0, 4.0)].map(|(x, y)| point_at(&mesh, x, y));
+        for end in ends {
+            mesh.insert_constraint(center, end).unwrap();
+        }
+        let triangles = mesh.triangles().count();
+
+        // three constraints meet at the center, one ends at each of the others
+        for point in [center, ends[0]] {
+            assert!(matches!(
+                mesh.remove_point(point),
+                Err(Nari2DCoreError::Locked(_))
+            ));
+        }
+        mesh.validate().unwrap();
+        assert_eq!(mesh.triangles().count(), triangles);
+        let locked = mesh.edges().filter(|(_, edge)| edge.locked).count();
+        assert_eq!(locked, 6);
+    }
+
+    #[test]
+    fn compact_round_trip_keeps_locks_and_holes() {
+        let mesh = holed();
+        let summary_before = summary(&mesh);
+        assert_eq!(summary_before.2.len(), 5);
+        assert_eq!(summary_before.3, [LoopKind::Outer, LoopKind::Hole]);
+
+        let loaded = EditMesh::from_compact(&mesh.to_compact().unwrap()).unwrap();
+        loaded.validate().unwrap();
+        assert_eq!(summary(&loaded), summary_before);
+        assert_eq!(loaded.holes(), mesh.holes());
+    }
+
+    #[cfg(feature = "serde_impl")]
+    #[test]
+    fn serde_round_trip_keeps_locks_and_holes() {
+        let mesh = holed();
+        let loaded: EditMesh =
+            serde_json::from_str(&serde_json::to_string(&mesh).unwrap()).unwrap();
+        loaded.validate().unwrap();
+        assert_eq!(summary(&loaded), summary(&mesh));
+        assert_eq!(loaded.holes(), mesh.holes());
+        // ids saved before still resolve
+        for (id, point) in mesh.points() {
+            assert_eq!(loaded.point(id), Some(point));
+        }
+    }
+}
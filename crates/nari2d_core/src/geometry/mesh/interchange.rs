@@ -0,0 +1,465 @@
+use crate::geometry::{
+    bounds::Bounds,
+    mesh::{simple_mesh::SimpleMesh, MeshImplementation},
+    point2d::Point2d,
+};
+use itertools::Itertools;
+use std::{collections::HashMap, fmt::Write};
+
+#[cfg(any(feature = "edit", feature = "serde_impl"))]
+use crate::error::{NCResult, Nari2DCoreError};
+#[cfg(feature = "edit")]
+use crate::geometry::mesh::edit_mesh::{CompactMesh, EditMesh, LoopKind};
+
+// reading and writing meshes in formats that other tools understand. none of them keep point
+// ids: points are written in the order of the compiled SimpleMesh, and read back in file order.
+// OBJ puts the origin of the texture at the bottom left instead of the top left, so v is
+// flipped on the way in and out.
+
+impl SimpleMesh {
+    // positions with z = 0, uvs and one face per triangle
+    #[must_use]
+    pub fn to_obj(&self) -> String {
+        let mut obj = String::from("# nari2d mesh\n");
+        for point in self.positions() {
+            let _ = writeln!(obj, "v {} {} 0", point.x, point.y);
+        }
+        for uv in self.uvs() {
+            let _ = writeln!(obj, "vt {} {}", uv.x, 1_f32 - uv.y);
+        }
+        for [a, b, c] in self.triangle_indices() {
+            let _ = writeln!(obj, "f {0}/{0} {1}/{1} {2}/{2}", a + 1, b + 1, c + 1);
+        }
+        obj
+    }
+
+    // a JsonMesh. fails if a position or uv is not finite, which JSON has no numbers for
+    /// # Errors
+    ///
+    /// `Nari2DCoreError::General` if a position or uv is not finite.
+    #[cfg(feature = "serde_impl")]
+    pub fn to_json(&self) -> NCResult<String> {
+        self.to_json_with(self.uvs().iter().copied().map(Some))
+    }
+
+    #[cfg(feature = "serde_impl")]
+    fn to_json_with(&self, uvs: impl Iterator<Item = Option<Point2d>>) -> NCResult<String> {
+        let pair = |point: Point2d| [point.x, point.y];
+        JsonMesh {
+            positions: self.positions().iter().copied().map(pair).collect(),
+            uvs: Some(uvs.map(|uv| uv.map(pair)).collect()),
+            indices: self
+                .indices()
+                .iter()
+                .map(u32::try_from)
+                .collect::<Result<_, _>>()
+                .map_err(|_| Nari2DCoreError::General("Too many vertices for JSON".into()))?,
+        }
+        .write()
+    }
+
+    // the triangles, with the edges that only belong to one triangle drawn on top
+    #[must_use]
+    pub fn to_svg(&self) -> String {
+        let mut uses: HashMap<(usize, usize), usize> = HashMap::new();
+        for [a, b, c] in self.triangle_indices() {
+            for (from, to) in [(a, b), (b, c), (c, a)] {
+                *uses.entry((from.min(to), from.max(to))).or_default() += 1;
+            }
+        }
+        let outlines = uses
+            .into_iter()
+            .filter(|(_, count)| *count == 1)
+            .filter_map(|((a, b), _)| {
+                let positions = self.positions();
+                Some(("outer", vec![*positions.get(a)?, *positions.get(b)?]))
+            })
+            .collect_vec();
+        svg(self.bounds(), self.triangle_vertices(), &outlines)
+    }
+}
+
+#[cfg(feature = "edit")]
+impl EditMesh {
+    #[must_use]
+    pub fn to_obj(&self) -> String {
+        self.compile().to_obj()
+    }
+
+    // like the compiled mesh, but points without a uv get null instead of (0, 0)
+    /// # Errors
+    ///
+    /// `Nari2DCoreError::General` if a position or uv is not finite.
+    #[cfg(feature = "serde_impl")]
+    pub fn to_json(&self) -> NCResult<String> {
+        let compiled = self.compile();
+        let uvs = (0..compiled.positions().len()).map(|vertex| {
+            compiled
+                .point_id(vertex)
+                .and_then(|id| self.uv(id))
+                .copied()
+        });
+        compiled.to_json_with(uvs)
+    }
+
+    // the triangles, with the outer boarder loops and holes drawn on top
+    #[must_use]
+    pub fn to_svg(&self) -> String {
+        let outlines = self
+            .boarder_loops()
+            .into_iter()
+            .map(|boarder_loop| {
+                let class = match boarder_loop.kind {
+                    LoopKind::Outer => "outer",
+                    LoopKind::Hole => "hole",
+                };
+                let points = boarder_loop
+                    .points
+                    .iter()
+                    .filter_map(|point| self.point(*point).copied())
+                    .collect_vec();
+                (class, points)
+            })
+            .collect_vec();
+        svg(self.bounds(), self.triangle_vertices(), &outlines)
+    }
+
+    // reads the vertices, texture coordinates and faces, ignoring everything else. faces with
+    // more than three corners are split into a fan, clockwise faces are turned around. the mesh
+    // is flattened onto whichever plane it lies in, see `flatten`.
+    /// # Errors
+    ///
+    /// - `Nari2DCoreError::Import` on a malformed line, an index that is out of range or a face
+    ///   without area.
+    /// - `Nari2DCoreError::DoesNotExist`, `Nari2DCoreError::NonManifoldStructure` or
+    ///   `Nari2DCoreError::InvalidMesh` if the faces don't form a valid mesh.
+    pub fn from_obj(obj: &str) -> NCResult<EditMesh> {
+        let mut points = vec![];
+        let mut texture = vec![];
+        let mut faces = vec![];
+        let mut corner_uvs = vec![];
+
+        for (number, line) in obj.lines().enumerate() {
+            let error = |why: &str| import_error(format!("line {}: {why}", number + 1));
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("v") => {
+                    let (Some(x), Some(y)) =
+                        (parse_float(tokens.next()), parse_float(tokens.next()))
+                    else {
+                        return Err(error("expected a vertex position"));
+                    };
+                    let z = tokens.next().map_or(Some(0_f32), |z| z.parse().ok());
+                    let z = z.ok_or_else(|| error("expected a vertex position"))?;
+                    points.push([x, y, z]);
+                }
+                Some("vt") => {
+                    let u = parse_float(tokens.next()).ok_or_else(|| error("expected a uv"))?;
+                    let v = tokens.next().map_or(Some(0_f32), |v| v.parse().ok());
+                    let v = v.ok_or_else(|| error("expected a uv"))?;
+                    texture.push(Point2d::new(u, 1_f32 - v));
+                }
+                Some("f") => {
+                    let corners = tokens
+                        .map(|corner| {
+                            let mut parts = corner.split('/');
+                            let point = obj_index(parts.next()?, points.len())?;
+                            let uv = match parts.next() {
+                                None | Some("") => None,
+                                Some(uv) => Some(obj_index(uv, texture.len())?),
+                            };
+                            Some((point, uv))
+                        })
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(|| error("malformed face"))?;
+                    if corners.len() < 3 {
+                        return Err(error("a face needs at least three corners"));
+                    }
+                    for (b, c) in corners[1..].iter().tuple_windows() {
+                        faces.push([corners[0].0, b.0, c.0]);
+                    }
+                    corner_uvs.extend(corners);
+                }
+                _ => {}
+            }
+        }
+
+        // OBJ gives uvs per corner, the first corner to use a point decides its uv
+        let mut uvs = vec![None; points.len()];
+        for (point, uv) in corner_uvs {
+            let Some((slot, uv)) = uvs.get_mut(point).zip(uv) else {
+                continue;
+            };
+            if slot.is_none() {
+                *slot = Some(
+                    *texture
+                        .get(uv)
+                        .ok_or_else(|| import_error(format!("uv {} does not exist", uv + 1)))?,
+                );
+            }
+        }
+
+        mesh_from_faces(flatten(&points), uvs, &faces)
+    }
+
+    // reads a JsonMesh, uvs are optional
+    /// # Errors
+    ///
+    /// - `Nari2DCoreError::Import` if the JSON is not a `JsonMesh`, the indices don't make whole
+    ///   triangles or are out of range, or a face has no area.
+    /// - `Nari2DCoreError::DoesNotExist`, `Nari2DCoreError::NonManifoldStructure` or
+    ///   `Nari2DCoreError::InvalidMesh` if the faces don't form a valid mesh.
+    #[cfg(feature = "serde_impl")]
+    pub fn from_json(json: &str) -> NCResult<EditMesh> {
+        let mesh =
+            serde_json::from_str::<JsonMesh>(json).map_err(|why| import_error(why.to_string()))?;
+        let point = |[x, y]: [f32; 2]| Point2d::new(x, y);
+
+        let points = mesh.positions.into_iter().map(point).collect_vec();
+        let uvs = match mesh.uvs {
+            None => vec![None; points.len()],
+            Some(uvs) if uvs.len() == points.len() => {
+                uvs.into_iter().map(|uv| uv.map(point)).collect()
+            }
+            Some(_) => {
+                return Err(import_error(
+                    "uvs and positions are not the same length".into(),
+                ))
+            }
+        };
+        if mesh.indices.len() % 3 != 0 {
+            return Err(import_error(
+                "the number of indices is not a multiple of 3".into(),
+            ));
+        }
+
+        let faces = mesh
+            .indices
+            .into_iter()
+            .map(|index| usize::try_from(index).unwrap_or(usize::MAX))
+            .tuples()
+            .map(|(a, b, c)| [a, b, c])
+            .collect_vec();
+        mesh_from_faces(points, uvs, &faces)
+    }
+}
+
+#[cfg(feature = "edit")]
+fn mesh_from_faces(
+    points: Vec<Point2d>,
+    uvs: Vec<Option<Point2d>>,
+    faces: &[[usize; 3]],
+) -> NCResult<EditMesh> {
+    let mut triangles = Vec::with_capacity(faces.len());
+    for face in faces {
+        let [Some(a), Some(b), Some(c)] = face.map(|index| points.get(index)) else {
+            return Err(import_error(
+                "a face uses a point that does not exist".into(),
+            ));
+        };
+        let [Some(i0), Some(i1), Some(i2)] = face.map(|index| u32::try_from(index).ok()) else {
+            return Err(import_error("too many points".into()));
+        };
+
+        let orientation = Point2d::orientation(a, b, c);
+        if orientation.is_counter_clock_wise() {
+            triangles.push([i0, i1, i2]);
+        } else if orientation.is_clockwise() {
+            triangles.push([i0, i2, i1]);
+        } else {
+            return Err(import_error("a face has no area".into()));
+        }
+    }
+
+    EditMesh::from_compact(&CompactMesh {
+        points,
+        uvs,
+        triangles,
+        locked_edges: vec![],
+        holes: vec![],
+        carve_exterior: false,
+    })
+}
+
+// the layout `to_json` writes and `from_json` reads:
+// {"positions": [[x, y], ..], "uvs": [[u, v] or null, ..], "indices": [a, b, c, ..]}
+// a point without a uv has null in its place, and uvs can be left out altogether.
+#[cfg(feature = "serde_impl")]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct JsonMesh {
+    pub positions: Vec<[f32; 2]>,
+    #[serde(default)]
+    pub uvs: Option<Vec<Option<[f32; 2]>>>,
+    pub indices: Vec<u32>,
+}
+
+#[cfg(feature = "serde_impl")]
+impl JsonMesh {
+    fn write(&self) -> NCResult<String> {
+        let finite = |pair: &[f32; 2]| pair.iter().all(|value| value.is_finite());
+        let uvs = self.uvs.iter().flatten().flatten();
+        if !self.positions.iter().chain(uvs).all(finite) {
+            return Err(Nari2DCoreError::General(
+                "A position or uv is not finite, JSON can not store it".into(),
+            ));
+        }
+        serde_json::to_string(self).map_err(|why| Nari2DCoreError::General(why.to_string().into()))
+    }
+}
+
+fn svg(
+    bounds: Bounds,
+    triangles: impl Iterator<Item = [Point2d; 3]>,
+    outlines: &[(&str, Vec<Point2d>)],
+) -> String {
+    let (min, size) = (bounds.min(), bounds.size());
+    let padding = size.x.max(size.y).max(1_f32) / 20_f32;
+    let polygon = |points: &[Point2d]| {
+        points
+            .iter()
+            .map(|point| format!("{},{}", point.x, point.y))
+            .join(" ")
+    };
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n",
+        min.x - padding,
+        min.y - padding,
+        size.x + padding * 2_f32,
+        size.y + padding * 2_f32
+    );
+    svg.push_str(
+        "<style>polygon{vector-effect:non-scaling-stroke;stroke-linejoin:round}\
+         .triangle{fill:#8ab4f8;fill-opacity:0.4;stroke:#1a4480;stroke-width:1}\
+         .outer{fill:none;stroke:#d93025;stroke-width:2}\
+         .hole{fill:none;stroke:#f29900;stroke-width:2}</style>\n",
+    );
+    for triangle in triangles {
+        let _ = writeln!(
+            svg,
+            "<polygon class=\"triangle\" points=\"{}\"/>",
+            polygon(&triangle)
+        );
+    }
+    for (class, points) in outlines {
+        let _ = writeln!(
+            svg,
+            "<polygon class=\"{class}\" points=\"{}\"/>",
+            polygon(points)
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// tools that export y up put a flat mesh in the xz plane instead of the xy one. the axis the
+// points spread out the least along is dropped, looking down it from the positive side. z wins
+// a tie, so meshes written by `to_obj` come back the way they were
+#[cfg(feature = "edit")]
+fn flatten(points: &[[f32; 3]]) -> Vec<Point2d> {
+    let spread = |axis: usize| {
+        let (min, max) = points
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), point| {
+                (min.min(point[axis]), max.max(point[axis]))
+            });
+        max - min
+    };
+    let (x, y, z) = (spread(0), spread(1), spread(2));
+    points
+        .iter()
+        .map(|&[px, py, pz]| {
+            if z <= x && z <= y {
+                Point2d::new(px, py)
+            } else if y <= x {
+                Point2d::new(px, -pz)
+            } else {
+                Point2d::new(py, pz)
+            }
+        })
+        .collect()
+}
+
+#[cfg(feature = "edit")]
+fn import_error(why: String) -> Nari2DCoreError {
+    Nari2DCoreError::Import(why.into())
+}
+
+#[cfg(feature = "edit")]
+fn parse_float(token: Option<&str>) -> Option<f32> {
+    token?.parse().ok()
+}
+
+// one based, negative indices count back from the last one read so far
+#[cfg(feature = "edit")]
+fn obj_index(token: &str, read: usize) -> Option<usize> {
+    let index = token.parse::<isize>().ok()?;
+    if index > 0 {
+        index.unsigned_abs().checked_sub(1)
+    } else {
+        read.checked_sub(index.unsigned_abs())
+            .filter(|_| index != 0)
+    }
+}
+
+#[cfg(all(test, feature = "edit"))]
+mod tests {
+    use super::*;
+
+    // a 5 by 5 grid with a locked square around the cell at (1, 1), carved out as a hole, and
+    // one more locked edge across the cell at (2, 2). uvs are quarters, which OBJ flips exactly
+    fn holed() -> EditMesh {
+        let mut mesh = EditMesh::new();
+        let ids = (0..25_u8)
+            .map(|i| mesh.insert_point(Point2d::new(f32::from(i % 5), f32::from(i / 5))))
+            .collect_vec();
+        let at = |x: usize, y: usize| ids[y * 5 + x];
+        let square = [at(1, 1), at(2, 1), at(2, 2), at(1, 2)];
+        let mut constraints = square.into_iter().circular_tuple_windows().collect_vec();
+        constraints.push((at(2, 3), at(3, 2)));
+        mesh.retriangulate_constrained(&constraints, &[Point2d::new(1.5, 1.5)], false)
+            .unwrap();
+        mesh.generate_uvs(Bounds::from_min_max(
+            Point2d::new(0_f32, 0_f32),
+            Point2d::new(4_f32, 4_f32),
+        ));
+        mesh
+    }
+
+    // OBJ and JSON only have the triangles, so the hole comes back as the triangles that are
+    // missing while the locks and hole points are gone
+    fn assert_same_triangles(loaded: &EditMesh, mesh: &EditMesh) {
+        loaded.validate().unwrap();
+        let (loaded_compiled, compiled) = (loaded.compile(), mesh.compile());
+        assert_eq!(loaded_compiled.positions(), compiled.positions());
+        assert_eq!(loaded_compiled.uvs(), compiled.uvs());
+        assert_eq!(loaded.triangles().count(), mesh.triangles().count());
+        let kinds = |mesh: &EditMesh| {
+            mesh.boarder_loops()
+                .into_iter()
+                .map(|boarder_loop| boarder_loop.kind)
+                .sorted_by_key(|kind| *kind == LoopKind::Hole)
+                .collect_vec()
+        };
+        assert_eq!(kinds(loaded), [LoopKind::Outer, LoopKind::Hole]);
+        assert!(loaded.edges().all(|(_, edge)| !edge.locked));
+        assert!(loaded.holes().is_empty());
+        assert!(loaded.points().all(|(id, _)| loaded.uv(id).is_some()));
+    }
+
+    #[test]
+    fn obj_round_trip_keeps_the_hole() {
+        let mesh = holed();
+        assert_same_triangles(&EditMesh::from_obj(&mesh.to_obj()).unwrap(), &mesh);
+    }
+
+    #[cfg(feature = "serde_impl")]
+    #[test]
+    fn json_round_trip_keeps_the_hole() {
+        let mesh = holed();
+        assert_same_triangles(
+            &EditMesh::from_json(&mesh.to_json().unwrap()).unwrap(),
+            &mesh,
+        );
+    }
+}
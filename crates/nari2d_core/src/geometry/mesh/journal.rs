@@ -0,0 +1,479 @@
+use crate::{
+    error::{NCResult, Nari2DCoreError},
+    geometry::{
+        mesh::{edit_mesh::EditMesh, EdgeId, PointId, Triangle, TriangleId},
+        point2d::Point2d,
+    },
+};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+};
+
+// undo and redo for edits to an EditMesh. while an edit runs, the mesh logs every change it
+// makes to its points, edges and triangles, and undo takes those back last first. undoing is
+// logged the same way, which is what redo takes back in turn.
+//
+// slotmap can't put an element back under the key it was removed from, so whatever undo or
+// redo puts back gets a new id. the journal remembers where every old id went, use
+// `resolve_point` and friends to follow ids held from before.
+
+// one change to an EditMesh, with what is needed to take it back
+#[derive(Clone, Debug)]
+pub(crate) enum Change {
+    InsertPoint(PointId),
+    RemovePoint {
+        id: PointId,
+        position: Point2d,
+        uv: Option<Point2d>,
+    },
+    MovePoint {
+        id: PointId,
+        from: Point2d,
+    },
+    SetUv {
+        id: PointId,
+        from: Option<Point2d>,
+    },
+    InsertEdge(EdgeId),
+    RemoveEdge {
+        id: EdgeId,
+        points: (PointId, PointId),
+        locked: bool,
+    },
+    LockEdge {
+        id: EdgeId,
+        was: bool,
+    },
+    ConnectTriangle(TriangleId),
+    DisconnectTriangle {
+        id: TriangleId,
+        triangle: Triangle,
+    },
+    SetHoles {
+        holes: Vec<Point2d>,
+        carve_exterior: bool,
+    },
+}
+
+// from the id something had before it was removed to the id it was put back under. an id can
+// be put back more than once, so this is followed until it ends
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Remap {
+    points: HashMap<PointId, PointId>,
+    edges: HashMap<EdgeId, EdgeId>,
+    triangles: HashMap<TriangleId, TriangleId>,
+}
+
+impl Remap {
+    pub(crate) fn point(&self, id: PointId) -> PointId {
+        follow(&self.points, id)
+    }
+
+    pub(crate) fn edge(&self, id: EdgeId) -> EdgeId {
+        follow(&self.edges, id)
+    }
+
+    pub(crate) fn triangle(&self, id: TriangleId) -> TriangleId {
+        follow(&self.triangles, id)
+    }
+
+    pub(crate) fn restore_point(&mut self, id: PointId, to: PointId) {
+        self.points.insert(id, to);
+    }
+
+    pub(crate) fn restore_edge(&mut self, id: EdgeId, to: EdgeId) {
+        self.edges.insert(id, to);
+    }
+
+    pub(crate) fn restore_triangle(&mut self, id: TriangleId, to: TriangleId) {
+        self.triangles.insert(id, to);
+    }
+}
+
+fn follow<K: Copy + Eq + Hash>(map: &HashMap<K, K>, mut id: K) -> K {
+    while let Some(to) = map.get(&id) {
+        id = *to;
+    }
+    id
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    name: Cow<'static, str>,
+    // in the order they were made
+    changes: Vec<Change>,
+}
+
+#[derive(Clone, Debug)]
+pub struct EditJournal {
+    mesh: EditMesh,
+    undo: VecDeque<Entry>,
+    redo: Vec<Entry>,
+    // every change since begin
+    transaction: Option<Entry>,
+    // the most entries that are kept, the oldest ones are dropped first
+    limit: usize,
+    remap: Remap,
+}
+
+impl EditJournal {
+    #[must_use]
+    pub fn new(mesh: EditMesh, limit: usize) -> Self {
+        EditJournal {
+            mesh,
+            undo: VecDeque::new(),
+            redo: vec![],
+            transaction: None,
+            limit,
+            remap: Remap::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn mesh(&self) -> &EditMesh {
+        &self.mesh
+    }
+
+    #[must_use]
+    pub fn into_mesh(self) -> EditMesh {
+        self.mesh
+    }
+
+    #[must_use]
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.trim();
+    }
+
+    // where a point ended up after undo or redo put it back, the id itself if it never left
+    #[must_use]
+    pub fn resolve_point(&self, id: PointId) -> PointId {
+        self.remap.point(id)
+    }
+
+    #[must_use]
+    pub fn resolve_edge(&self, id: EdgeId) -> EdgeId {
+        self.remap.edge(id)
+    }
+
+    #[must_use]
+    pub fn resolve_triangle(&self, id: TriangleId) -> TriangleId {
+        self.remap.triangle(id)
+    }
+
+    #[must_use]
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || self.transaction.is_some()
+    }
+
+    #[must_use]
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    // the name of the edit that undo would revert
+    #[must_use]
+    pub fn undo_name(&self) -> Option<&str> {
+        self.transaction
+            .as_ref()
+            .or_else(|| self.undo.back())
+            .map(|entry| entry.name.as_ref())
+    }
+
+    #[must_use]
+    pub fn redo_name(&self) -> Option<&str> {
+        self.redo.last().map(|entry| entry.name.as_ref())
+    }
+
+    #[must_use]
+    pub fn in_transaction(&self) -> bool {
+        self.transaction.is_some()
+    }
+
+    // runs the edit and records it. if it fails the mesh is put back the way it was and nothing
+    // is recorded, inside of a transaction that means the whole transaction is rolled back.
+    /// # Errors
+    ///
+    /// The error of the edit, or `Nari2DCoreError::DoesNotExist` or
+    /// `Nari2DCoreError::NonManifoldStructure` if putting the mesh back fails, see
+    /// `EditJournal::undo`.
+    pub fn edit<T>(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        edit: impl FnOnce(&mut EditMesh) -> NCResult<T>,
+    ) -> NCResult<T> {
+        self.mesh.record();
+        let result = edit(&mut self.mesh);
+        let changes = self.mesh.take_changes();
+
+        if let Some(transaction) = &mut self.transaction {
+            transaction.changes.extend(changes);
+            if result.is_err() {
+                self.rollback()?;
+            }
+            return result;
+        }
+
+        match result {
+            Ok(value) => {
+                self.push(Entry {
+                    name: name.into(),
+                    changes,
+                });
+                Ok(value)
+            }
+            Err(why) => {
+                self.revert(&changes)?;
+                Err(why)
+            }
+        }
+    }
+
+    // groups every edit until commit into one entry
+    /// # Errors
+    ///
+    /// `Nari2DCoreError::General` if a transaction is already open.
+    pub fn begin(&mut self, name: impl Into<Cow<'static, str>>) -> NCResult<()> {
+        if self.transaction.is_some() {
+            return Err(Nari2DCoreError::General(
+                "A transaction is already open".into(),
+            ));
+        }
+        self.transaction = Some(Entry {
+            name: name.into(),
+            changes: vec![],
+        });
+        Ok(())
+    }
+
+    /// # Errors
+    ///
+    /// `Nari2DCoreError::General` if no transaction is open.
+    pub fn commit(&mut self) -> NCResult<()> {
+        let entry = self
+            .transaction
+            .take()
+            .ok_or_else(|| Nari2DCoreError::General("No transaction is open".into()))?;
+        self.push(entry);
+        Ok(())
+    }
+
+    // throws away every edit since begin. if that fails the transaction stays open and the mesh
+    // is left as it was
+    /// # Errors
+    ///
+    /// - `Nari2DCoreError::General` if no transaction is open.
+    /// - `Nari2DCoreError::DoesNotExist` or `Nari2DCoreError::NonManifoldStructure` if the edits
+    ///   can't be reverted, see `EditJournal::undo`.
+    pub fn rollback(&mut self) -> NCResult<()> {
+        let entry = self
+            .transaction
+            .take()
+            .ok_or_else(|| Nari2DCoreError::General("No transaction is open".into()))?;
+        if let Err(why) = self.revert(&entry.changes) {
+            self.transaction = Some(entry);
+            return Err(why);
+        }
+        Ok(())
+    }
+
+    // reverts the last edit, an open transaction is rolled back instead. false if there was
+    // nothing to undo. fails if the edit could not be reverted, the mesh and the history are
+    // then left as they were
+    /// # Errors
+    ///
+    /// - `Nari2DCoreError::DoesNotExist` if a point, edge or triangle the edit recorded is gone,
+    ///   which means the mesh was changed outside of the journal.
+    /// - `Nari2DCoreError::NonManifoldStructure` if a triangle can't be put back for the same
+    ///   reason.
+    pub fn undo(&mut self) -> NCResult<bool> {
+        if self.transaction.is_some() {
+            self.rollback()?;
+            return Ok(true);
+        }
+        let Some(entry) = self.undo.pop_back() else {
+            return Ok(false);
+        };
+        let changes = match self.revert(&entry.changes) {
+            Ok(changes) => changes,
+            Err(why) => {
+                self.undo.push_back(entry);
+                return Err(why);
+            }
+        };
+        self.redo.push(Entry {
+            name: entry.name,
+            changes,
+        });
+        Ok(true)
+    }
+
+    // applies the last undone edit again. false if there was nothing to redo, or a transaction
+    // is open. fails like undo does
+    /// # Errors
+    ///
+    /// `Nari2DCoreError::DoesNotExist` or `Nari2DCoreError::NonManifoldStructure`, like
+    /// `EditJournal::undo`.
+    pub fn redo(&mut self) -> NCResult<bool> {
+        if self.transaction.is_some() {
+            return Ok(false);
+        }
+        let Some(entry) = self.redo.pop() else {
+            return Ok(false);
+        };
+        let changes = match self.revert(&entry.changes) {
+            Ok(changes) => changes,
+            Err(why) => {
+                self.redo.push(entry);
+                return Err(why);
+            }
+        };
+        self.undo.push_back(Entry {
+            name: entry.name,
+            changes,
+        });
+        self.trim();
+        Ok(true)
+    }
+
+    // forgets the history, keeping the mesh as it is. ids put back by undo or redo still
+    // resolve
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    // takes the changes back, returning what that changed so it can be taken back as well. if
+    // one of them can't be, the mesh is left as it was
+    fn revert(&mut self, changes: &[Change]) -> NCResult<Vec<Change>> {
+        self.mesh.record();
+        let result = self.mesh.revert(changes, &mut self.remap);
+        let reverted = self.mesh.take_changes();
+        if let Err(why) = result {
+            // put back what was taken back before the change that failed, so nothing is half
+            // reverted
+            self.mesh.revert(&reverted, &mut self.remap)?;
+            return Err(why);
+        }
+        Ok(reverted)
+    }
+
+    fn push(&mut self, entry: Entry) {
+        self.redo.clear();
+        self.undo.push_back(entry);
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.undo.len() > self.limit {
+            self.undo.pop_front();
+        }
+        if self.redo.len() > self.limit {
+            self.redo.drain(..self.redo.len() - self.limit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> EditMesh {
+        let mut mesh = EditMesh::new();
+        for i in 0..16_u8 {
+            mesh.insert_point(Point2d::new(f32::from(i % 4), f32::from(i / 4)));
+        }
+        mesh.retriangulate_mesh().unwrap();
+        mesh
+    }
+
+    #[test]
+    fn failed_undo_leaves_mesh_and_history_alone() {
+        let mut journal = EditJournal::new(grid(), 16);
+        let point = journal.mesh().points().next().unwrap().0;
+        let edge = journal.mesh().edges().next().unwrap().0;
+        let uv = Point2d::new(0.25, 0.75);
+        journal
+            .edit("lock and set uv", |mesh| {
+                mesh.set_edge_locked(edge, true)?;
+                mesh.set_uv(point, uv)
+            })
+            .unwrap();
+
+        // the uv is taken back first, then the lock fails on an edge that is not there
+        let entry = journal.undo.back_mut().unwrap();
+        assert!(matches!(entry.changes[0], Change::LockEdge { .. }));
+        entry.changes[0] = Change::LockEdge {
+            id: EdgeId::default(),
+            was: false,
+        };
+
+        assert!(journal.undo().is_err());
+        assert_eq!(journal.mesh().uv(point), Some(&uv));
+        assert!(journal.mesh().edge(edge).unwrap().locked);
+        assert_eq!(journal.undo.len(), 1);
+        assert!(journal.redo.is_empty());
+        assert_eq!(journal.undo_name(), Some("lock and set uv"));
+        journal.mesh().validate().unwrap();
+    }
+
+    #[test]
+    fn undo_without_history_is_not_an_error() {
+        let mut journal = EditJournal::new(grid(), 16);
+        assert!(!journal.undo().unwrap());
+        assert!(!journal.redo().unwrap());
+        assert_eq!(journal.mesh().points().count(), 16);
+    }
+
+    #[test]
+    fn undo_and_redo_keep_the_mesh_valid() {
+        let mut journal = EditJournal::new(grid(), 16);
+        let original = (
+            journal.mesh().points().count(),
+            journal.mesh().triangles().count(),
+        );
+        let edge = journal
+            .mesh()
+            .edges()
+            .find(|(_, e)| !e.is_boarder())
+            .unwrap()
+            .0;
+        let point = journal.mesh().closest(&Point2d::new(1.0, 1.0)).unwrap();
+
+        journal
+            .edit("split and remove", |mesh| {
+                mesh.split_edge(edge)?;
+                mesh.remove_point(point)
+            })
+            .unwrap();
+        journal.mesh().validate().unwrap();
+        let edited = (
+            journal.mesh().points().count(),
+            journal.mesh().triangles().count(),
+        );
+
+        for _ in 0..2 {
+            assert!(journal.undo().unwrap());
+            journal.mesh().validate().unwrap();
+            let counts = (
+                journal.mesh().points().count(),
+                journal.mesh().triangles().count(),
+            );
+            assert_eq!(counts, original);
+
+            assert!(journal.redo().unwrap());
+            journal.mesh().validate().unwrap();
+            let counts = (
+                journal.mesh().points().count(),
+                journal.mesh().triangles().count(),
+            );
+            assert_eq!(counts, edited);
+        }
+    }
+}
@@ -5,14 +5,24 @@
 // };
 // use std::{cmp::Ordering, ops::Add};
 
+use crate::geometry::{bounds::Bounds, point2d::Point2d};
 use smallvec::SmallVec;
 use std::fmt::{Display, Formatter};
 
+#[cfg(feature = "edit")]
+pub mod alpha_mesh;
+#[cfg(feature = "edit")]
+pub mod binding;
 #[cfg(feature = "edit")]
 pub mod edit_mesh;
+pub mod interchange;
+#[cfg(feature = "edit")]
+pub mod journal;
+pub mod picking;
 pub mod simple_mesh;
 
 #[derive(Copy, Clone, Debug, Default, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "serde_impl", derive(Serialize, Deserialize))]
 pub struct Triangle {
     pub point0: PointId,
     pub point1: PointId,
@@ -23,6 +33,27 @@ impl Triangle {
     pub fn contains(&self, id: PointId) -> bool {
         self.point0 == id || self.point1 == id || self.point2 == id
     }
+
+    #[must_use]
+    pub fn points(&self) -> [PointId; 3] {
+        [self.point0, self.point1, self.point2]
+    }
+
+    // rotates the triangle so that a -> b is its first side, keeping the winding.
+    // the returned triangle has the point opposite of a -> b as point2
+    #[must_use]
+    pub fn rotated_to(&self, a: PointId, b: PointId) -> Option<Triangle> {
+        let [p0, p1, p2] = self.points();
+        if p0 == a && p1 == b {
+            Some([p0, p1, p2].into())
+        } else if p1 == a && p2 == b {
+            Some([p1, p2, p0].into())
+        } else if p2 == a && p0 == b {
+            Some([p2, p0, p1].into())
+        } else {
+            None
+        }
+    }
 }
 
 impl From<[PointId; 3]> for Triangle {
@@ -46,11 +77,14 @@ impl From<(PointId, PointId, PointId)> for Triangle {
 }
 
 #[derive(Copy, Clone, Debug, Default, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "serde_impl", derive(Serialize, Deserialize))]
 pub struct Edge {
     pub point0: PointId,
     pub point1: PointId,
     pub triangle0: Option<TriangleId>,
     pub triangle1: Option<TriangleId>,
+    // constraint edges are never flipped or removed by retriangulation
+    pub locked: bool,
 }
 
 impl Edge {
@@ -58,6 +92,49 @@ impl Edge {
         self.point0 == point || self.point1 == point
     }
 
+    #[must_use]
+    pub fn other_point(&self, point: PointId) -> Option<PointId> {
+        if self.point0 == point {
+            Some(self.point1)
+        } else if self.point1 == point {
+            Some(self.point0)
+        } else {
+            None
+        }
+    }
+
+    #[must_use]
+    pub fn other_triangle(&self, triangle: TriangleId) -> Option<TriangleId> {
+        if self.triangle0 == Some(triangle) {
+            self.triangle1
+        } else if self.triangle1 == Some(triangle) {
+            self.triangle0
+        } else {
+            None
+        }
+    }
+
+    #[must_use]
+    pub fn triangle_count(&self) -> usize {
+        usize::from(self.triangle0.is_some()) + usize::from(self.triangle1.is_some())
+    }
+
+    #[must_use]
+    pub fn is_boarder(&self) -> bool {
+        self.triangle_count() == 1
+    }
+
+    pub fn remove_triangle(&mut self, triangle: TriangleId) -> Option<()> {
+        if self.triangle0 == Some(triangle) {
+            self.triangle0 = self.triangle1.take();
+        } else if self.triangle1 == Some(triangle) {
+            self.triangle1 = None;
+        } else {
+            return None;
+        }
+        Some(())
+    }
+
     pub fn add_triangle_unoccupied(&mut self, new: TriangleId) -> Option<()> {
         match self.triangle0 {
             None => {
@@ -75,6 +152,7 @@ impl Edge {
 }
 
 #[derive(Clone, Debug, Default, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "serde_impl", derive(Serialize, Deserialize))]
 pub struct PointEdge {
     pub edges: SmallVec<[EdgeId; 6]>,
 }
@@ -99,7 +177,10 @@ impl From<[EdgeId; 6]> for PointEdge {
     }
 }
 
+// edges[0] is point0 -> point1, edges[1] is point1 -> point2 and edges[2] is point2 -> point0
+// of the triangle with the same id.
 #[derive(Clone, Debug, Default, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "serde_impl", derive(Serialize, Deserialize))]
 pub struct TriangleEdge {
     pub edges: [EdgeId; 3],
 }
@@ -132,5 +213,14 @@ impl Display for EdgeId {
     }
 }
 
-// impl triangle mesh
-pub trait MeshImplementation {}
+// the read side shared by every mesh, so renderers and deformers don't care which one they get
+pub trait MeshImplementation {
+    fn vertex_count(&self) -> usize;
+
+    fn triangle_count(&self) -> usize;
+
+    // the positions of every triangle's points, counter clockwise
+    fn triangle_vertices(&self) -> impl Iterator<Item = [Point2d; 3]> + '_;
+
+    fn bounds(&self) -> Bounds;
+}
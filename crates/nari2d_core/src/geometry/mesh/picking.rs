@@ -0,0 +1,142 @@
+use crate::geometry::{mesh::simple_mesh::SimpleMesh, point2d::Point2d};
+use itertools::Itertools;
+
+#[cfg(feature = "edit")]
+use crate::geometry::mesh::{
+    edit_mesh::{EditMesh, Located},
+    EdgeId, PointId, TriangleId,
+};
+
+// what is under the cursor. the edit mesh answers with ids, the runtime mesh with indices into
+// its vertex and index buffers.
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EdgeHit<E> {
+    pub edge: E,
+    pub distance: f32,
+    // where on the edge the closest point is, 0 at its first point and 1 at its second
+    pub parameter: f32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TriangleHit<T> {
+    pub triangle: T,
+    // the weights of the triangle's points, in order, that add up to the picked point
+    pub barycentric: [f32; 3],
+}
+
+impl SimpleMesh {
+    // the closest vertex at most `radius` away
+    #[must_use]
+    pub fn pick_vertex(&self, point: Point2d, radius: f32) -> Option<usize> {
+        self.positions()
+            .iter()
+            .enumerate()
+            .map(|(vertex, position)| (vertex, position.distance2(&point)))
+            .filter(|(_, distance2)| *distance2 <= radius * radius)
+            .min_by(|(_, a), (_, b)| f32::total_cmp(a, b))
+            .map(|(vertex, _)| vertex)
+    }
+
+    // the closest edge at most `radius` away, as the two vertices of the edge
+    #[must_use]
+    pub fn pick_edge(&self, point: Point2d, radius: f32) -> Option<EdgeHit<[usize; 2]>> {
+        let positions = self.positions();
+        self.triangle_indices()
+            .flat_map(|[a, b, c]| [[a, b], [b, c], [c, a]])
+            .filter_map(|[a, b]| {
+                let (distance, parameter) =
+                    edge_distance(point, *positions.get(a)?, *positions.get(b)?);
+                Some(EdgeHit {
+                    edge: [a, b],
+                    distance,
+                    parameter,
+                })
+            })
+            .filter(|hit| hit.distance <= radius)
+            .min_by(|a, b| f32::total_cmp(&a.distance, &b.distance))
+    }
+
+    // the triangle the point is in, as the index of the triangle in `triangle_indices`
+    #[must_use]
+    pub fn pick_triangle(&self, point: Point2d) -> Option<TriangleHit<usize>> {
+        let positions = self.positions();
+        self.triangle_indices()
+            .enumerate()
+            .find_map(|(triangle, [a, b, c])| {
+                let corners = [*positions.get(a)?, *positions.get(b)?, *positions.get(c)?];
+                let inside = corners
+                    .iter()
+                    .circular_tuple_windows()
+                    .all(|(from, to)| !Point2d::orientation(from, to, &point).is_clockwise());
+                if !inside {
+                    return None;
+                }
+                Some(TriangleHit {
+                    triangle,
+                    barycentric: point.barycentric(&corners[0], &corners[1], &corners[2])?,
+                })
+            })
+    }
+}
+
+#[cfg(feature = "edit")]
+impl EditMesh {
+    // the closest point at most `radius` away
+    #[must_use]
+    pub fn pick_point(&self, point: Point2d, radius: f32) -> Option<PointId> {
+        self.closest_n(&point, 1)?.into_iter().find(|id| {
+            self.point(*id)
+                .is_some_and(|position| position.distance2(&point) <= radius * radius)
+        })
+    }
+
+    // the closest edge at most `radius` away
+    #[must_use]
+    pub fn pick_edge(&self, point: Point2d, radius: f32) -> Option<EdgeHit<EdgeId>> {
+        self.edges()
+            .filter_map(|(id, edge)| {
+                let (distance, parameter) =
+                    edge_distance(point, *self.point(edge.point0)?, *self.point(edge.point1)?);
+                Some(EdgeHit {
+                    edge: id,
+                    distance,
+                    parameter,
+                })
+            })
+            .filter(|hit| hit.distance <= radius)
+            .min_by(|a, b| f32::total_cmp(&a.distance, &b.distance))
+    }
+
+    // the triangle the point is in. on an edge or a point any of the triangles touching it is
+    // picked
+    #[must_use]
+    pub fn pick_triangle(&self, point: Point2d) -> Option<TriangleHit<TriangleId>> {
+        let triangle = match self.locate(point) {
+            Located::InTriangle(triangle) => triangle,
+            Located::OnEdge(edge) => {
+                let edge = self.edge(edge)?;
+                edge.triangle0.or(edge.triangle1)?
+            }
+            Located::OnVertex(vertex) => self
+                .point_edge(vertex)?
+                .edges()
+                .iter()
+                .filter_map(|edge| self.edge(*edge))
+                .find_map(|edge| edge.triangle0.or(edge.triangle1))?,
+            Located::Outside => return None,
+        };
+
+        let [a, b, c] = self.triangle(triangle)?.points();
+        Some(TriangleHit {
+            triangle,
+            barycentric: point.barycentric(self.point(a)?, self.point(b)?, self.point(c)?)?,
+        })
+    }
+}
+
+// (distance, parameter)
+fn edge_distance(point: Point2d, a: Point2d, b: Point2d) -> (f32, f32) {
+    let (closest, parameter) = point.closest_on_segment(&a, &b);
+    (point.distance(&closest), parameter)
+}
@@ -1,12 +1,188 @@
 use crate::geometry::point2d::Point2d;
 use crate::geometry::{
-    mesh::{PointId, Triangle, TriangleId},
+    bounds::Bounds,
+    mesh::{MeshImplementation, PointId},
 };
-use slotmap::SlotMap;
+use itertools::Either;
+use slotmap::SecondaryMap;
 
+#[cfg(feature = "edit")]
+use crate::geometry::mesh::edit_mesh::EditMesh;
+
+// indices into the vertex arrays, three per triangle. u16 is used when every vertex fits
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IndexBuffer {
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
+impl IndexBuffer {
+    #[must_use]
+    pub fn len(&self) -> usize {
+        match self {
+            IndexBuffer::U16(indices) => indices.len(),
+            IndexBuffer::U32(indices) => indices.len(),
+        }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<usize> {
+        match self {
+            IndexBuffer::U16(indices) => indices.get(index).map(|i| usize::from(*i)),
+            IndexBuffer::U32(indices) => indices.get(index).and_then(|i| usize::try_from(*i).ok()),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        match self {
+            IndexBuffer::U16(indices) => Either::Left(indices.iter().map(|i| usize::from(*i))),
+            IndexBuffer::U32(indices) => {
+                Either::Right(indices.iter().filter_map(|i| usize::try_from(*i).ok()))
+            }
+        }
+    }
+}
+
+impl Default for IndexBuffer {
+    fn default() -> Self {
+        IndexBuffer::U16(vec![])
+    }
+}
+
+// the frozen, render ready form of a mesh. vertices are stored in the order the triangles first
+// use them, so neighbouring triangles are also close in memory.
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct SimpleMesh {
-    points: SlotMap<PointId, Point2d>,
-    triangles: SlotMap<TriangleId, Triangle>,
+    positions: Vec<Point2d>,
+    // same length as positions, (0, 0) for points that had no uv
+    uvs: Vec<Point2d>,
+    indices: IndexBuffer,
+    // the edit mesh point every vertex came from and back, so animation data authored against
+    // the edit mesh still resolves
+    point_ids: Vec<PointId>,
+    vertices: SecondaryMap<PointId, usize>,
+    bounds: Bounds,
+}
+
+impl SimpleMesh {
+    #[must_use]
+    pub fn positions(&self) -> &[Point2d] {
+        &self.positions
+    }
+
+    #[must_use]
+    pub fn uvs(&self) -> &[Point2d] {
+        &self.uvs
+    }
+
+    #[must_use]
+    pub fn indices(&self) -> &IndexBuffer {
+        &self.indices
+    }
+
+    #[must_use]
+    pub fn vertex_of(&self, id: PointId) -> Option<usize> {
+        self.vertices.get(id).copied()
+    }
+
+    #[must_use]
+    pub fn point_id(&self, vertex: usize) -> Option<PointId> {
+        self.point_ids.get(vertex).copied()
+    }
+
+    #[must_use]
+    pub fn position(&self, id: PointId) -> Option<&Point2d> {
+        self.positions.get(self.vertex_of(id)?)
+    }
+
+    #[must_use]
+    pub fn uv(&self, id: PointId) -> Option<&Point2d> {
+        self.uvs.get(self.vertex_of(id)?)
+    }
+
+    pub fn triangle_indices(&self) -> impl Iterator<Item = [usize; 3]> + '_ {
+        let mut indices = self.indices.iter();
+        std::iter::from_fn(move || Some([indices.next()?, indices.next()?, indices.next()?]))
+    }
+}
+
+impl MeshImplementation for SimpleMesh {
+    fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    fn triangle_vertices(&self) -> impl Iterator<Item = [Point2d; 3]> + '_ {
+        self.triangle_indices().filter_map(|[a, b, c]| {
+            Some([
+                *self.positions.get(a)?,
+                *self.positions.get(b)?,
+                *self.positions.get(c)?,
+            ])
+        })
+    }
+
+    fn bounds(&self) -> Bounds {
+        self.bounds
+    }
 }
 
-impl SimpleMesh {}
+#[cfg(feature = "edit")]
+impl From<&EditMesh> for SimpleMesh {
+    fn from(mesh: &EditMesh) -> Self {
+        let mut simple = SimpleMesh {
+            bounds: mesh.bounds(),
+            ..SimpleMesh::default()
+        };
+        let mut indices = Vec::with_capacity(mesh.triangle_count() * 3);
+
+        let vertex = |simple: &mut SimpleMesh, id: PointId| {
+            if let Some(existing) = simple.vertices.get(id) {
+                return *existing;
+            }
+            let index = simple.positions.len();
+            simple
+                .positions
+                .push(mesh.point(id).copied().unwrap_or_default());
+            simple.uvs.push(mesh.uv(id).copied().unwrap_or_default());
+            simple.point_ids.push(id);
+            simple.vertices.insert(id, index);
+            index
+        };
+
+        for (_, triangle) in mesh.triangles() {
+            for point in triangle.points() {
+                indices.push(vertex(&mut simple, point));
+            }
+        }
+        // points that are not part of any triangle still get a vertex, so every id resolves
+        for (id, _) in mesh.points() {
+            vertex(&mut simple, id);
+        }
+
+        simple.indices = if u16::try_from(simple.positions.len()).is_ok() {
+            IndexBuffer::U16(
+                indices
+                    .into_iter()
+                    .filter_map(|i| u16::try_from(i).ok())
+                    .collect(),
+            )
+        } else {
+            IndexBuffer::U32(
+                indices
+                    .into_iter()
+                    .filter_map(|i| u32::try_from(i).ok())
+                    .collect(),
+            )
+        };
+        simple
+    }
+}
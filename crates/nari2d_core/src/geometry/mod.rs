@@ -1,7 +1,10 @@
 pub mod angle;
 pub mod bounds;
+pub mod deformer;
 pub mod lattice;
 pub mod mesh;
 pub mod orientation;
 pub mod point2d;
+pub mod polygon;
 pub mod scale2d;
+pub mod transform2d;
@@ -20,12 +20,13 @@ impl Orientation {
 
 impl From<f64> for Orientation {
     fn from(v: f64) -> Self {
-        if v.is_sign_negative() {
+        // 0.0 is sign positive, check it first
+        if v == 0_f64 {
+            Orientation::Colinear
+        } else if v.is_sign_negative() {
             Orientation::ClockWise
-        } else if v.is_sign_positive() {
-            Orientation::CounterClockWise
         } else {
-            Orientation::Colinear
+            Orientation::CounterClockWise
         }
     }
 }
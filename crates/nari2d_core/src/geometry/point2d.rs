@@ -1,12 +1,12 @@
-use crate::geometry::orientation::Orientation;
+use crate::geometry::{orientation::Orientation, polygon, scale2d::Scale2d};
 use cgmath::{MetricSpace, Point2};
 use float_eq::float_eq;
-use robust::{orient2d, Coord};
+use robust::{incircle, orient2d, Coord};
 use std::{
     cmp::Ordering,
     fmt::{Display, Formatter},
     hash::{Hash, Hasher},
-    ops::{Deref, DerefMut},
+    ops::{Add, Deref, DerefMut, Div, Mul, Sub},
 };
 
 #[derive(Copy, Clone, Debug)]
@@ -65,6 +65,14 @@ impl Point2d {
         self.y
     }
 
+    pub fn set_x(&mut self, x: f32) {
+        self.x = x;
+    }
+
+    pub fn set_y(&mut self, y: f32) {
+        self.y = y;
+    }
+
     pub fn line_intersect(a: (&Point2d, &Point2d), b: (&Point2d, &Point2d)) -> bool {
         let o1 = Point2d::orientation(a.0, a.1, b.0);
         let o2 = Point2d::orientation(a.0, a.1, b.1);
@@ -90,6 +98,12 @@ impl Point2d {
         orient2d(a.into(), b.into(), c.into()).into()
     }
 
+    // p1, p2 and p3 must be in counter clockwise order. points on the circle are not inside.
+    #[must_use]
+    pub fn in_circumcircle(&self, p1: &Point2d, p2: &Point2d, p3: &Point2d) -> bool {
+        incircle(p1.into(), p2.into(), p3.into(), self.into()) > 0_f64
+    }
+
     pub fn is_on_segment(&self, segment: (&Point2d, &Point2d)) -> bool {
         if self.x <= f32::max(segment.0.x, segment.1.x)
             && self.x >= f32::min(segment.0.x, segment.1.x)
@@ -102,42 +116,9 @@ impl Point2d {
         }
     }
 
+    // non zero winding, points on the outline are inside
     pub fn is_inside(&self, polygon: &[Point2d]) -> bool {
-        if polygon.len() < 3 {
-            return false;
-        }
-
-        let extreme = Point2d::new(f32::INFINITY, self.y);
-        let mut decrease = 0;
-
-        let mut count = 0;
-        let mut i = 0;
-        loop {
-            let next = (i + 1) % polygon.len();
-            if polygon.get(i).map(Point2d::y) == Some(self.y) {
-                decrease += 1;
-            }
-
-            let a = some_or_continue!(polygon.get(i));
-            let b = some_or_continue!(polygon.get(next));
-
-            if Point2d::line_intersect((a, b), (&self, &extreme)) {
-                if Point2d::orientation(a, &self, b) == Orientation::Colinear {
-                    return self.is_on_segment((a, b));
-                }
-                count += 1;
-            }
-
-            i = next;
-
-            if i == 0 {
-                break;
-            }
-        }
-
-        count -= decrease;
-
-        return count % 2 == 1;
+        polygon.len() >= 3 && polygon::contains(polygon, *self)
     }
 
     pub fn distance(&self, other: &Point2d) -> f32 {
@@ -148,6 +129,31 @@ impl Point2d {
         self.int.distance2(other.int)
     }
 
+    // the point on the segment a - b that is closest to this one, along with how far along the
+    // segment it is, 0 at a and 1 at b
+    #[must_use]
+    pub fn closest_on_segment(&self, a: &Point2d, b: &Point2d) -> (Point2d, f32) {
+        let ab = *b - *a;
+        let length2 = ab.x * ab.x + ab.y * ab.y;
+        if length2 == 0_f32 {
+            return (*a, 0_f32);
+        }
+        let along = (((self.x - a.x) * ab.x + (self.y - a.y) * ab.y) / length2).clamp(0_f32, 1_f32);
+        (*a + ab * along, along)
+    }
+
+    // weights of a, b and c that add up to this point. None for degenerate triangles
+    #[must_use]
+    pub fn barycentric(&self, a: &Point2d, b: &Point2d, c: &Point2d) -> Option<[f32; 3]> {
+        let det = (b.y - c.y) * (a.x - c.x) + (c.x - b.x) * (a.y - c.y);
+        if det == 0_f32 {
+            return None;
+        }
+        let weight_a = ((b.y - c.y) * (self.x - c.x) + (c.x - b.x) * (self.y - c.y)) / det;
+        let weight_b = ((c.y - a.y) * (self.x - c.x) + (a.x - c.x) * (self.y - c.y)) / det;
+        Some([weight_a, weight_b, 1_f32 - weight_a - weight_b])
+    }
+
     pub fn circumradius2(p1: &Point2d, p2: &Point2d, p3: &Point2d) -> f32 {
         let circumdelta = Point2d::circumdelta(p1, p2, p3);
         circumdelta.x().powi(2) + circumdelta.y().powi(2)
@@ -211,6 +217,38 @@ impl Hash for Point2d {
     }
 }
 
+impl Add for Point2d {
+    type Output = Point2d;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Point2d::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub for Point2d {
+    type Output = Point2d;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Point2d::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl Mul<f32> for Point2d {
+    type Output = Point2d;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        Point2d::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl Div<f32> for Point2d {
+    type Output = Point2d;
+
+    fn div(self, rhs: f32) -> Self::Output {
+        Point2d::new(self.x / rhs, self.y / rhs)
+    }
+}
+
 impl From<Point2<f32>> for Point2d {
     fn from(p2: Point2<f32>) -> Self {
         Point2d { int: p2 }
@@ -223,6 +261,12 @@ impl From<Point2d> for Point2<f32> {
     }
 }
 
+impl From<Point2d> for Scale2d {
+    fn from(p2d: Point2d) -> Self {
+        Scale2d::new(p2d.x, p2d.y)
+    }
+}
+
 impl From<Point2d> for Coord<f32> {
     fn from(p2d: Point2d) -> Self {
         Coord {
@@ -247,7 +291,7 @@ impl rstar::Point for Point2d {
 
     const DIMENSIONS: usize = 2;
 
-    fn generate(generator: impl FnMut(usize) -> Self::Scalar) -> Self {
+    fn generate(mut generator: impl FnMut(usize) -> Self::Scalar) -> Self {
         Self::new(generator(0), generator(1))
     }
 
@@ -0,0 +1,533 @@
+use crate::geometry::{bounds::Bounds, orientation::Orientation, point2d::Point2d};
+use itertools::Itertools;
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    f32::consts::PI,
+};
+
+// a closed outline, the last point connects back to the first. counter clockwise polygons have
+// a positive area, following the y up convention of the rest of geometry.
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde_impl", derive(Serialize, Deserialize))]
+pub struct Polygon {
+    points: Vec<Point2d>,
+}
+
+impl Polygon {
+    #[must_use]
+    pub fn new(points: Vec<Point2d>) -> Self {
+        Polygon { points }
+    }
+
+    #[must_use]
+    pub fn points(&self) -> &[Point2d] {
+        &self.points
+    }
+
+    #[must_use]
+    pub fn into_points(self) -> Vec<Point2d> {
+        self.points
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    // every side, including the one from the last point back to the first
+    pub fn edges(&self) -> impl Iterator<Item = (Point2d, Point2d)> + '_ {
+        self.points.iter().copied().circular_tuple_windows()
+    }
+
+    #[must_use]
+    pub fn bounds(&self) -> Option<Bounds> {
+        Bounds::from_points(self.points.iter().copied())
+    }
+
+    // positive if counter clockwise
+    #[must_use]
+    pub fn signed_area(&self) -> f32 {
+        self.edges()
+            .map(|(a, b)| a.x * b.y - b.x * a.y)
+            .sum::<f32>()
+            / 2_f32
+    }
+
+    #[must_use]
+    pub fn area(&self) -> f32 {
+        self.signed_area().abs()
+    }
+
+    // the center of mass of the area, None if there is no area
+    #[must_use]
+    pub fn centroid(&self) -> Option<Point2d> {
+        let area = self.signed_area();
+        if area == 0_f32 {
+            return None;
+        }
+        // relative to the first point, so far away polygons don't lose precision
+        let origin = *self.points.first()?;
+        let sum = self
+            .edges()
+            .map(|(a, b)| (a - origin, b - origin))
+            .fold(Point2d::default(), |sum, (a, b)| {
+                sum + (a + b) * (a.x * b.y - b.x * a.y)
+            });
+        Some(origin + sum / (area * 6_f32))
+    }
+
+    // colinear if there is no area
+    #[must_use]
+    pub fn winding(&self) -> Orientation {
+        f64::from(self.signed_area()).into()
+    }
+
+    pub fn reverse(&mut self) {
+        self.points.reverse();
+    }
+
+    // reverses the points if they go the other way. a colinear winding leaves them as they are
+    pub fn set_winding(&mut self, winding: Orientation) {
+        let current = self.winding();
+        if !winding.is_colinear() && !current.is_colinear() && current != winding {
+            self.reverse();
+        }
+    }
+
+    // every corner turns the same way and the outline goes around once. points in a straight
+    // line are allowed
+    #[must_use]
+    pub fn is_convex(&self) -> bool {
+        if self.points.len() < 3 {
+            return false;
+        }
+
+        let mut turning = None;
+        let mut total = 0_f32;
+        for (prev, current, next) in self.points.iter().circular_tuple_windows() {
+            let orientation = Point2d::orientation(prev, current, next);
+            if orientation.is_colinear() {
+                continue;
+            }
+            if *turning.get_or_insert(orientation) != orientation {
+                return false;
+            }
+            let (into, out) = (*current - *prev, *next - *current);
+            total += (into.x * out.y - into.y * out.x).atan2(into.x * out.x + into.y * out.y);
+        }
+        // a star turns the same way at every corner too, but goes around more than once
+        turning.is_some() && (total.abs() - PI * 2_f32).abs() < PI
+    }
+
+    // how many times the outline goes counter clockwise around the point, minus the times it
+    // goes clockwise
+    #[must_use]
+    pub fn winding_number(&self, point: Point2d) -> i32 {
+        winding_number(&self.points, point)
+    }
+
+    // inside with the non zero rule. points on the outline are inside
+    #[must_use]
+    pub fn contains(&self, point: Point2d) -> bool {
+        contains(&self.points, point)
+    }
+
+    #[must_use]
+    pub fn on_outline(&self, point: Point2d) -> bool {
+        self.edges().any(|(a, b)| on_segment(point, a, b))
+    }
+
+    // douglas peucker, keeping every point further than `tolerance` from the simplified
+    // outline. the outline is split at the point furthest from the first one
+    #[must_use]
+    pub fn simplify_douglas_peucker(&self, tolerance: f32) -> Polygon {
+        let outline = &self.points;
+        if outline.len() < 4 {
+            return self.clone();
+        }
+
+        let Some((furthest, _)) = outline.iter().enumerate().max_by(|(_, a), (_, b)| {
+            f32::total_cmp(&outline[0].distance2(a), &outline[0].distance2(b))
+        }) else {
+            return self.clone();
+        };
+
+        let mut keep = vec![false; outline.len()];
+        keep[0] = true;
+        keep[furthest] = true;
+
+        let mut stack = vec![(0, furthest), (furthest, outline.len())];
+        while let Some((start, end)) = stack.pop() {
+            let a = outline[start];
+            let b = outline[end % outline.len()];
+            let Some((index, distance)) = (start + 1..end)
+                .map(|index| (index, segment_distance(outline[index], a, b)))
+                .max_by(|(_, d1), (_, d2)| f32::total_cmp(d1, d2))
+            else {
+                continue;
+            };
+
+            if distance > tolerance {
+                keep[index] = true;
+                stack.push((start, index));
+                stack.push((index, end));
+            }
+        }
+
+        outline
+            .iter()
+            .zip(keep)
+            .filter_map(|(point, keep)| keep.then_some(*point))
+            .collect()
+    }
+
+    // visvalingam whyatt, removing the point that makes the smallest triangle with its
+    // neighbours until every triangle is at least `min_area`. never goes below 3 points
+    #[must_use]
+    pub fn simplify_visvalingam(&self, min_area: f32) -> Polygon {
+        let count = self.points.len();
+        if count <= 3 {
+            return self.clone();
+        }
+
+        let mut prev = (0..count)
+            .map(|index| (index + count - 1) % count)
+            .collect_vec();
+        let mut next = (0..count).map(|index| (index + 1) % count).collect_vec();
+        let mut removed = vec![false; count];
+        // bumped every time a neighbour of the point goes away, so old areas can be told apart
+        let mut version = vec![0_usize; count];
+        let area = |prev: usize, index: usize, next: usize| {
+            let (a, b, c) = (self.points[prev], self.points[index], self.points[next]);
+            ((b.x - a.x) * (c.y - a.y) - (c.x - a.x) * (b.y - a.y)).abs() / 2_f32
+        };
+
+        let mut heap = (0..count)
+            .map(|index| Reverse(Candidate(area(prev[index], index, next[index]), index, 0)))
+            .collect::<BinaryHeap<_>>();
+        let mut left = count;
+        while let Some(Reverse(Candidate(smallest, index, stamp))) = heap.pop() {
+            if left <= 3 || smallest >= min_area {
+                break;
+            }
+            if removed[index] || stamp != version[index] {
+                continue;
+            }
+
+            removed[index] = true;
+            left -= 1;
+            let (before, after) = (prev[index], next[index]);
+            next[before] = after;
+            prev[after] = before;
+            for neighbour in [before, after] {
+                version[neighbour] += 1;
+                heap.push(Reverse(Candidate(
+                    area(prev[neighbour], neighbour, next[neighbour]),
+                    neighbour,
+                    version[neighbour],
+                )));
+            }
+        }
+
+        self.points
+            .iter()
+            .zip(removed)
+            .filter_map(|(point, removed)| (!removed).then_some(*point))
+            .collect()
+    }
+
+    // moves every side `distance` outwards, or inwards if it is negative, whichever way the
+    // polygon winds. sharp corners are cut off instead of reaching out more than `miter_limit`
+    // times the distance. deflating past a narrow part leaves the sides crossing each other.
+    // the outline starts at the corner of the first point, which a cut corner adds two points for
+    #[must_use]
+    pub fn offset(&self, distance: f32, miter_limit: f32) -> Polygon {
+        let points = self
+            .points
+            .iter()
+            .copied()
+            .dedup()
+            .collect_vec()
+            .into_iter()
+            .circular_tuple_windows()
+            .filter(|(a, b)| a != b)
+            .map(|(a, _)| a)
+            .collect_vec();
+        if points.len() < 3 || distance == 0_f32 {
+            return Polygon::new(points);
+        }
+
+        // the right of a counter clockwise side is outside
+        let outwards = if self.signed_area() < 0_f32 {
+            -distance
+        } else {
+            distance
+        };
+        let normal = |a: Point2d, b: Point2d| {
+            let direction = (b - a) / a.distance(&b);
+            Point2d::new(direction.y, -direction.x)
+        };
+
+        let mut offset = Vec::with_capacity(points.len());
+        let count = points.len();
+        for index in 0..count {
+            let (prev, current, next) = (
+                points[(index + count - 1) % count],
+                points[index],
+                points[(index + 1) % count],
+            );
+            let (before, after) = (normal(prev, current), normal(current, next));
+            let bisector = before + after;
+            let length2 = bisector.x * bisector.x + bisector.y * bisector.y;
+            // how far along the bisector the corner moves, relative to the distance
+            let reach = if length2 <= f32::EPSILON {
+                f32::INFINITY
+            } else {
+                2_f32 / length2.sqrt()
+            };
+
+            if reach <= miter_limit {
+                offset.push(current + bisector * (2_f32 / length2 * outwards));
+            } else {
+                offset.push(current + before * outwards);
+                offset.push(current + after * outwards);
+            }
+        }
+        Polygon::new(offset)
+    }
+}
+
+impl From<Vec<Point2d>> for Polygon {
+    fn from(points: Vec<Point2d>) -> Self {
+        Polygon::new(points)
+    }
+}
+
+impl From<&[Point2d]> for Polygon {
+    fn from(points: &[Point2d]) -> Self {
+        Polygon::new(points.to_vec())
+    }
+}
+
+impl FromIterator<Point2d> for Polygon {
+    fn from_iter<T: IntoIterator<Item = Point2d>>(iter: T) -> Self {
+        Polygon::new(iter.into_iter().collect())
+    }
+}
+
+// sunday's winding number, with the side tests done by robust's orient2d
+pub(crate) fn winding_number(outline: &[Point2d], point: Point2d) -> i32 {
+    outline
+        .iter()
+        .circular_tuple_windows()
+        .map(|(a, b)| {
+            if a.y <= point.y {
+                // going up with the point on the left
+                i32::from(
+                    b.y > point.y && Point2d::orientation(a, b, &point).is_counter_clock_wise(),
+                )
+            } else if b.y <= point.y && Point2d::orientation(a, b, &point).is_clockwise() {
+                // going down with the point on the right
+                -1
+            } else {
+                0
+            }
+        })
+        .sum()
+}
+
+pub(crate) fn contains(outline: &[Point2d], point: Point2d) -> bool {
+    outline
+        .iter()
+        .circular_tuple_windows()
+        .any(|(a, b)| on_segment(point, *a, *b))
+        || winding_number(outline, point) != 0
+}
+
+fn on_segment(point: Point2d, a: Point2d, b: Point2d) -> bool {
+    Point2d::orientation(&a, &b, &point).is_colinear() && point.is_on_segment((&a, &b))
+}
+
+fn segment_distance(point: Point2d, a: Point2d, b: Point2d) -> f32 {
+    point.distance(&point.closest_on_segment(&a, &b).0)
+}
+
+// an area ordered by total_cmp, with the index and version of its point
+#[derive(Copy, Clone, Debug)]
+struct Candidate(f32, usize, usize);
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        f32::total_cmp(&self.0, &other.0)
+            .then(self.1.cmp(&other.1))
+            .then(self.2.cmp(&other.2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn polygon(points: &[(f32, f32)]) -> Polygon {
+        points.iter().map(|(x, y)| Point2d::new(*x, *y)).collect()
+    }
+
+    // an L, with the notch at the top right
+    fn concave() -> Polygon {
+        polygon(&[
+            (0.0, 0.0),
+            (4.0, 0.0),
+            (4.0, 2.0),
+            (2.0, 2.0),
+            (2.0, 4.0),
+            (0.0, 4.0),
+        ])
+    }
+
+    // two squares meeting at (2, 2), going through it twice
+    fn self_touching() -> Polygon {
+        polygon(&[
+            (0.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 2.0),
+            (4.0, 2.0),
+            (4.0, 4.0),
+            (2.0, 4.0),
+            (2.0, 2.0),
+            (0.0, 2.0),
+        ])
+    }
+
+    #[test]
+    fn winding_number_and_containment() {
+        let mut concave = concave();
+        assert_eq!(concave.winding(), Orientation::CounterClockWise);
+        assert_eq!(concave.winding_number(Point2d::new(1.0, 3.0)), 1);
+        assert_eq!(concave.winding_number(Point2d::new(3.0, 3.0)), 0);
+        assert!(!concave.contains(Point2d::new(3.0, 3.0)));
+        assert!(concave.contains(Point2d::new(2.0, 3.0)));
+
+        concave.set_winding(Orientation::ClockWise);
+        assert_eq!(concave.winding_number(Point2d::new(1.0, 3.0)), -1);
+        assert!(concave.contains(Point2d::new(1.0, 3.0)));
+
+        let touching = self_touching();
+        assert!((touching.signed_area() - 8.0).abs() < 1e-6);
+        assert_eq!(touching.winding_number(Point2d::new(1.0, 1.0)), 1);
+        assert_eq!(touching.winding_number(Point2d::new(3.0, 3.0)), 1);
+        assert_eq!(touching.winding_number(Point2d::new(3.0, 1.0)), 0);
+        assert_eq!(touching.winding_number(Point2d::new(1.0, 3.0)), 0);
+        assert!(touching.contains(Point2d::new(2.0, 2.0)));
+        assert!(touching.on_outline(Point2d::new(2.0, 2.0)));
+        assert!(!touching.contains(Point2d::new(1.0, 3.0)));
+        assert!(!touching.is_convex());
+    }
+
+    #[test]
+    fn simplifying_drops_the_noise_and_keeps_the_corners() {
+        let noisy_concave = polygon(&[
+            (0.0, 0.0),
+            (2.0, 0.05),
+            (4.0, 0.0),
+            (4.0, 1.0),
+            (4.0, 2.0),
+            (3.0, 2.02),
+            (2.0, 2.0),
+            (2.0, 4.0),
+            (1.0, 4.0),
+            (0.0, 4.0),
+        ]);
+        assert_eq!(noisy_concave.simplify_douglas_peucker(0.1), concave());
+        assert_eq!(noisy_concave.simplify_visvalingam(0.2), concave());
+        // nothing is small enough to go
+        assert_eq!(concave().simplify_douglas_peucker(0.1), concave());
+        assert_eq!(concave().simplify_visvalingam(0.2), concave());
+
+        let noisy_touching = polygon(&[
+            (0.0, 0.0),
+            (2.0, 0.0),
+            (2.03, 1.0),
+            (2.0, 2.0),
+            (4.0, 2.0),
+            (4.0, 4.0),
+            (3.0, 3.98),
+            (2.0, 4.0),
+            (2.0, 2.0),
+            (0.0, 2.0),
+        ]);
+        assert_eq!(
+            noisy_touching.simplify_douglas_peucker(0.1),
+            self_touching()
+        );
+        assert_eq!(noisy_touching.simplify_visvalingam(0.2), self_touching());
+    }
+
+    // every side of a mitered offset lies on its side of the polygon moved by the distance
+    fn assert_sides_moved(polygon: &Polygon, offset: &Polygon, distance: f32) {
+        assert_eq!(offset.len(), polygon.len());
+        for ((a, b), (moved_a, moved_b)) in polygon.edges().zip(offset.edges()) {
+            let direction = (b - a) / a.distance(&b);
+            // positive to the right, which is outside of a counter clockwise polygon
+            let side = |point: Point2d| {
+                let from = point - a;
+                from.x * direction.y - from.y * direction.x
+            };
+            assert!(
+                (side(moved_a) - distance).abs() < 1e-4 && (side(moved_b) - distance).abs() < 1e-4,
+                "side {a:?} {b:?} went to {moved_a:?} {moved_b:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn offsetting_moves_every_side() {
+        let concave = concave();
+        let inflated = concave.offset(0.5, 4.0);
+        assert_eq!(
+            inflated,
+            polygon(&[
+                (-0.5, -0.5),
+                (4.5, -0.5),
+                (4.5, 2.5),
+                (2.5, 2.5),
+                (2.5, 4.5),
+                (-0.5, 4.5)
+            ])
+        );
+        assert_sides_moved(&concave, &inflated, 0.5);
+        assert_sides_moved(&concave, &concave.offset(-0.5, 4.0), -0.5);
+
+        // the same outwards whichever way it winds
+        let mut clockwise = concave.clone();
+        clockwise.set_winding(Orientation::ClockWise);
+        assert!((clockwise.offset(0.5, 4.0).area() - inflated.area()).abs() < 1e-4);
+
+        // every right angle reaches out sqrt 2 times the distance, past a miter limit of 1
+        assert_eq!(concave.offset(0.5, 1.0).len(), concave.len() * 2);
+
+        let touching = self_touching();
+        assert_sides_moved(&touching, &touching.offset(0.25, 4.0), 0.25);
+        assert_sides_moved(&touching, &touching.offset(-0.25, 4.0), -0.25);
+    }
+}
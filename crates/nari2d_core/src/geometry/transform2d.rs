@@ -0,0 +1,282 @@
+use crate::geometry::{angle::Angle, bounds::Bounds, point2d::Point2d, scale2d::Scale2d};
+use cgmath::{Matrix2, Matrix3, SquareMatrix, Vector2};
+use std::{f32::consts::PI, ops::Mul};
+
+// a point is scaled, skewed and rotated around the pivot, then moved by the translation:
+//
+// p' = translation + pivot + R * K * S * (p - pivot)
+//
+// where K shears x along y by the tangent of the skew. any affine transform that does not
+// flatten everything onto a line can be written this way, which is what from_matrix does.
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_impl", derive(Serialize, Deserialize))]
+pub struct Transform2d {
+    pub translation: Point2d,
+    pub rotation: Angle,
+    pub scale: Scale2d,
+    pub skew: Angle,
+    pub pivot: Point2d,
+}
+
+impl Transform2d {
+    pub const IDENTITY: Transform2d = Transform2d {
+        translation: Point2d::ZERO,
+        rotation: Angle::ZERO,
+        scale: Scale2d::new(1_f32, 1_f32),
+        skew: Angle::ZERO,
+        pivot: Point2d::ZERO,
+    };
+
+    #[must_use]
+    pub fn new(translation: Point2d, rotation: Angle, scale: Scale2d) -> Self {
+        Transform2d {
+            translation,
+            rotation,
+            scale,
+            ..Transform2d::IDENTITY
+        }
+    }
+
+    #[must_use]
+    pub fn from_translation(translation: Point2d) -> Self {
+        Transform2d {
+            translation,
+            ..Transform2d::IDENTITY
+        }
+    }
+
+    #[must_use]
+    pub fn from_rotation(rotation: Angle) -> Self {
+        Transform2d {
+            rotation,
+            ..Transform2d::IDENTITY
+        }
+    }
+
+    #[must_use]
+    pub fn from_scale(scale: Scale2d) -> Self {
+        Transform2d {
+            scale,
+            ..Transform2d::IDENTITY
+        }
+    }
+
+    #[must_use]
+    pub fn with_skew(self, skew: Angle) -> Self {
+        Transform2d { skew, ..self }
+    }
+
+    #[must_use]
+    pub fn with_pivot(self, pivot: Point2d) -> Self {
+        Transform2d { pivot, ..self }
+    }
+
+    // the same transform around another pivot. the translation makes up for the move, so every
+    // point still ends up in the same place
+    #[must_use]
+    pub fn repivot(&self, pivot: Point2d) -> Self {
+        let moved = self.linear() * Vector2::new(pivot.x - self.pivot.x, pivot.y - self.pivot.y);
+        Transform2d {
+            translation: self.translation + self.pivot - pivot + Point2d::new(moved.x, moved.y),
+            pivot,
+            ..*self
+        }
+    }
+
+    // R * K * S, the part that does not move the origin
+    #[must_use]
+    pub fn linear(&self) -> Matrix2<f32> {
+        let (sin, cos) = self.rotation.0.sin_cos();
+        let shear = self.skew.0.tan();
+        Matrix2::new(
+            cos * self.scale.x,
+            sin * self.scale.x,
+            self.scale.y * (cos * shear - sin),
+            self.scale.y * (sin * shear + cos),
+        )
+    }
+
+    // the homogeneous matrix, column major like the rest of cgmath
+    #[must_use]
+    pub fn to_matrix(&self) -> Matrix3<f32> {
+        let linear = self.linear();
+        let origin = self.transform_point(Point2d::ZERO);
+        Matrix3::new(
+            linear.x.x, linear.x.y, 0_f32, linear.y.x, linear.y.y, 0_f32, origin.x, origin.y, 1_f32,
+        )
+    }
+
+    // splits an affine matrix back into translation, rotation, skew and scale with the pivot at
+    // the origin. a mirrored matrix gets a negative y scale. None if the matrix is not affine or
+    // flattens everything onto a line or a point
+    #[must_use]
+    pub fn from_matrix(matrix: Matrix3<f32>) -> Option<Self> {
+        if matrix.x.z != 0_f32 || matrix.y.z != 0_f32 || matrix.z.z == 0_f32 {
+            return None;
+        }
+        let matrix = matrix / matrix.z.z;
+
+        let scale_x = matrix.x.x.hypot(matrix.x.y);
+        if scale_x <= f32::EPSILON {
+            return None;
+        }
+        let rotation = matrix.x.y.atan2(matrix.x.x);
+        // the y axis with the rotation taken out of it is (scale_y * tan(skew), scale_y)
+        let (sin, cos) = rotation.sin_cos();
+        let sheared = cos * matrix.y.x + sin * matrix.y.y;
+        let scale_y = cos * matrix.y.y - sin * matrix.y.x;
+        if scale_y.abs() <= f32::EPSILON {
+            return None;
+        }
+
+        Some(Transform2d {
+            translation: Point2d::new(matrix.z.x, matrix.z.y),
+            rotation: Angle::new(rotation),
+            scale: Scale2d::new(scale_x, scale_y),
+            skew: Angle::new((sheared / scale_y).atan()),
+            pivot: Point2d::ZERO,
+        })
+    }
+
+    // `inner` first, then this. the result has its pivot at the origin
+    #[must_use]
+    pub fn compose(&self, inner: &Transform2d) -> Option<Self> {
+        Self::from_matrix(self.to_matrix() * inner.to_matrix())
+    }
+
+    // the transform that puts every point back. None if this one flattens everything, which
+    // happens when a scale is zero or the skew is a right angle
+    #[must_use]
+    pub fn inverse(&self) -> Option<Self> {
+        Self::from_matrix(self.to_matrix().invert()?)
+    }
+
+    #[must_use]
+    pub fn transform_point(&self, point: Point2d) -> Point2d {
+        let moved = self.linear() * Vector2::new(point.x - self.pivot.x, point.y - self.pivot.y);
+        self.translation + self.pivot + Point2d::new(moved.x, moved.y)
+    }
+
+    // a direction or offset, which the translation and pivot do not apply to
+    #[must_use]
+    pub fn transform_vector(&self, vector: Scale2d) -> Scale2d {
+        self.linear() * vector
+    }
+
+    // the smallest bounds around the transformed corners
+    #[must_use]
+    pub fn transform_bounds(&self, bounds: &Bounds) -> Bounds {
+        bounds.transformed(self)
+    }
+
+    // blends every part on its own, so a half way rotation stays a rotation instead of
+    // shrinking like a blended matrix would. the rotation goes the short way around
+    #[must_use]
+    pub fn interpolate(&self, other: &Transform2d, t: f32) -> Self {
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        let mut turn = (other.rotation.0 - self.rotation.0) % (PI * 2_f32);
+        if turn > PI {
+            turn -= PI * 2_f32;
+        } else if turn < -PI {
+            turn += PI * 2_f32;
+        }
+
+        Transform2d {
+            translation: self.translation + (other.translation - self.translation) * t,
+            rotation: Angle::new(self.rotation.0 + turn * t),
+            scale: Scale2d::new(
+                lerp(self.scale.x, other.scale.x),
+                lerp(self.scale.y, other.scale.y),
+            ),
+            skew: Angle::new(lerp(self.skew.0, other.skew.0)),
+            pivot: self.pivot + (other.pivot - self.pivot) * t,
+        }
+    }
+}
+
+impl Default for Transform2d {
+    fn default() -> Self {
+        Transform2d::IDENTITY
+    }
+}
+
+impl Mul<Point2d> for Transform2d {
+    type Output = Point2d;
+
+    fn mul(self, rhs: Point2d) -> Self::Output {
+        self.transform_point(rhs)
+    }
+}
+
+impl From<Transform2d> for Matrix3<f32> {
+    fn from(transform: Transform2d) -> Self {
+        transform.to_matrix()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transform() -> Transform2d {
+        Transform2d::new(
+            Point2d::new(3_f32, -1.5),
+            Angle::new(0.8),
+            Scale2d::new(2_f32, -0.5),
+        )
+        .with_skew(Angle::new(0.3))
+        .with_pivot(Point2d::new(-2_f32, 4_f32))
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    fn same_points(a: &Transform2d, b: &Transform2d) {
+        for (x, y) in [
+            (0_f32, 0_f32),
+            (1_f32, 0_f32),
+            (0_f32, 1_f32),
+            (-3_f32, 5_f32),
+        ] {
+            let point = Point2d::new(x, y);
+            let (moved_a, moved_b) = (a.transform_point(point), b.transform_point(point));
+            assert!(
+                moved_a.distance(&moved_b) < 1e-4,
+                "{point:?} went to {moved_a:?} and {moved_b:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn transform_and_its_inverse_are_the_identity() {
+        let transform = transform();
+        let inverse = transform.inverse().unwrap();
+        for composed in [
+            transform.compose(&inverse).unwrap(),
+            inverse.compose(&transform).unwrap(),
+        ] {
+            same_points(&composed, &Transform2d::IDENTITY);
+            assert!(close(composed.rotation.0, 0_f32));
+            assert!(close(composed.skew.0, 0_f32));
+            assert!(close(composed.scale.x, 1_f32) && close(composed.scale.y, 1_f32));
+            assert!(composed.translation.distance(&Point2d::ZERO) < 1e-4);
+        }
+    }
+
+    #[test]
+    fn decompose_gives_back_the_parts() {
+        let transform = transform();
+        let decomposed = Transform2d::from_matrix(transform.to_matrix()).unwrap();
+
+        // the pivot is folded into the translation, everything else comes back as it was
+        let expected = transform.repivot(Point2d::ZERO);
+        assert!(decomposed.translation.distance(&expected.translation) < 1e-4);
+        assert!(close(decomposed.rotation.0, transform.rotation.0));
+        assert!(close(decomposed.skew.0, transform.skew.0));
+        assert!(close(decomposed.scale.x, transform.scale.x));
+        assert!(close(decomposed.scale.y, transform.scale.y));
+        same_points(&decomposed, &transform);
+    }
+}
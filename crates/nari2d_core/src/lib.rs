@@ -1,6 +1,5 @@
 #![deny(clippy::pedantic)]
 #![warn(clippy::all)]
-#![feature(iter_collect_into)]
 
 // chapter 8 - the core
 // painful/10, would play again
@@ -9,6 +8,7 @@
 #[macro_use]
 extern crate serde;
 
+#[cfg(feature = "edit")]
 #[macro_use]
 extern crate nari2d_macros;
 
@@ -1,4 +1,5 @@
 use euclid::Point2D;
+use nari2d_core::geometry::point2d::Point2d;
 use std::ops::{Deref, DerefMut};
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -84,3 +85,15 @@ impl From<Point2D<f32, f32>> for PositionComponent {
         PositionComponent::from(point.to_array())
     }
 }
+
+impl From<Point2d> for PositionComponent {
+    fn from(point: Point2d) -> Self {
+        PositionComponent::new(point.x, point.y)
+    }
+}
+
+impl From<PositionComponent> for Point2d {
+    fn from(position: PositionComponent) -> Self {
+        Point2d::new(position.position_x(), position.position_y())
+    }
+}
@@ -1,4 +1,5 @@
 use euclid::{Angle, Rotation2D, UnknownUnit};
+use nari2d_core::geometry::angle::Angle as CoreAngle;
 use std::ops::{Deref, DerefMut};
 
 #[derive(Copy, Clone, PartialEq)]
@@ -56,3 +57,15 @@ impl From<Rotation2D<f32, UnknownUnit, UnknownUnit>> for RotationComponent {
         RotationComponent::new(rotation.angle.to_radians())
     }
 }
+
+impl From<CoreAngle> for RotationComponent {
+    fn from(angle: CoreAngle) -> Self {
+        RotationComponent::new(angle.0)
+    }
+}
+
+impl From<RotationComponent> for CoreAngle {
+    fn from(rotation: RotationComponent) -> Self {
+        CoreAngle::new(rotation.radians())
+    }
+}
@@ -1,4 +1,5 @@
 use euclid::Vector2D;
+use nari2d_core::geometry::scale2d::Scale2d;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct ScaleComponent {
@@ -41,3 +42,15 @@ impl Default for ScaleComponent {
         ScaleComponent::new(1.0, 1.0)
     }
 }
+
+impl From<Scale2d> for ScaleComponent {
+    fn from(scale: Scale2d) -> Self {
+        ScaleComponent::new(scale.x, scale.y)
+    }
+}
+
+impl From<ScaleComponent> for Scale2d {
+    fn from(scale: ScaleComponent) -> Self {
+        Scale2d::new(scale.scale_x(), scale.scale_y())
+    }
+}